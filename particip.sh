@@ -2,8 +2,10 @@
 
 set -e
 
-ROSTER_PATH=~/docs/teaching/f24_3411/roster.csv
-NEW_ROSTER_PATH=${ROSTER_PATH}.out
+# The roster comes from the participation.toml in the current
+# directory, or from ~/.config/participation/config.toml, unless
+# ROSTER_PATH is set.
+ROSTER_ARGS=${ROSTER_PATH:+--roster $ROSTER_PATH}
 
 usage()
 {
@@ -13,18 +15,16 @@ usage()
 
 case $1 in
     run )
-	cargo run $ROSTER_PATH
+	cargo run -- $ROSTER_ARGS
 	;;
     rotate )
-	date=$(date +"%Y-%m-%d")
-	# save the old one
-	mv $ROSTER_PATH $ROSTER_PATH.$date
-	# update it to the new one reflecting updates
-	mv $NEW_ROSTER_PATH $ROSTER_PATH
+	# archive the old roster and its log, and promote the output
+	cargo run -- $ROSTER_ARGS commit
 	;;
     rm )
 	# remove the new, updated file...useful for testing
-	rm ${ROSTER_PATH}.out
+	rm ${ROSTER_PATH:?set ROSTER_PATH to the roster}.out
+	rm -f ${ROSTER_PATH}.log
 	;;
     * )
 	usage
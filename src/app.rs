@@ -0,0 +1,954 @@
+// The state of a class session, and everything that can be done in
+// it, independent of how it is shown. The terminal UI drives this, and
+// so can any other frontend or a test.
+
+use std::{
+    collections::{HashMap, HashSet},
+    ops::{Deref, DerefMut},
+};
+
+use fuzzy_matcher::skim::SkimMatcherV2;
+use fuzzy_matcher::FuzzyMatcher;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    attendance::{self, Attendance},
+    calling,
+    cooldown::{self, Cooldown},
+    events::{self, Counter, Outcome},
+    notes,
+    selection::{self, Draw, SelectionStrategy},
+    store::{self, Store},
+    Student, StudentKey, NUM_COLORS,
+};
+
+pub enum InputMode {
+    Command,
+    Searching,
+    Student,
+    Detail,
+    Editing,
+    RollCall,
+    Quitting,
+}
+
+#[derive(Clone)]
+pub enum DisplayMode {
+    Command,
+    Searching,
+}
+
+/// The sections being taught, each with its own roster, and which one
+/// is on screen. Derefs to that section, so most of the time an `App`
+/// can be used as if there were just the one.
+pub struct App {
+    pub sections: Vec<Section>,
+    /// The offset of the section on screen into `sections`
+    pub current: usize,
+    /// Asking whether to commit the sections before quitting
+    pub quitting: bool,
+}
+
+/// How a section picks students, and what can be recorded for them.
+#[derive(Debug, Clone)]
+pub struct Options {
+    /// The selection strategy's spec, e.g. `biased` or `decayed:14d`
+    pub strategy: String,
+    /// Seed the draws, to repeat a session's orders
+    pub seed: Option<u64>,
+    /// How long whoever was called stays at the bottom of the list
+    pub cooldown: Option<Cooldown>,
+    pub outcomes: Vec<Outcome>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            strategy: "biased".to_string(),
+            seed: None,
+            cooldown: None,
+            outcomes: events::default_outcomes(),
+        }
+    }
+}
+
+/// A section holds the state of one roster
+pub struct Section {
+    /// What the section is called on its tab
+    pub name: String,
+    /// Where the students and their events are kept
+    store: Box<dyn Store>,
+    /// Current value of the input box
+    pub input: String,
+    /// Position of cursor in the editor area.
+    pub character_index: usize,
+    /// How the main screen should render
+    pub display_mode: DisplayMode,
+    /// Display the selected student in a popout
+    pub student_display: Option<Student>,
+    /// Everything about the selected student, just to look at
+    pub detail: Option<Detail>,
+    /// A student's notes or tags being edited
+    pub editing: Option<Editing>,
+    /// All students, indexed by github id
+    pub students: HashMap<StudentKey, Student>,
+    /// The order to display students outside of search mode
+    order: Vec<StudentKey>,
+    /// How `order` is chosen
+    pub strategy: Box<dyn SelectionStrategy>,
+    /// The strategy asked for on the command line
+    configured_strategy: String,
+    /// How long before someone who was called can come up again
+    cooldown: Option<Cooldown>,
+    /// Who is cooling down, and so at the bottom of `order`
+    pub cooling: HashSet<StudentKey>,
+    /// What a student can be recorded with
+    pub outcomes: Vec<Outcome>,
+    /// Students who wanted a warning, and have had it, but haven't been
+    /// called on since
+    warned: HashSet<StudentKey>,
+    /// Who was just warned they are next
+    pub heads_up: Option<StudentKey>,
+    /// Where the seed for each draw of the `order` comes from
+    rng: StdRng,
+    /// The filtered, sorted view of students
+    view: Vec<StudentKey>,
+    /// The offset of the selected entry into the view
+    pub selection: Option<usize>,
+    /// Identifies this run of the program in the `store`'s events
+    pub session: String,
+    /// Actions in this session that can be undone, most recent last
+    undo: Vec<Action>,
+    /// Actions that have been undone and can be redone
+    redo: Vec<Action>,
+    /// What just happened, for the status line
+    pub status: Option<Status>,
+    /// Has anything been logged this session?
+    pub dirty: bool,
+    /// Everyone, by name, for taking attendance
+    pub roll: Vec<StudentKey>,
+    /// The offset into `roll` while taking attendance
+    pub roll_call: Option<usize>,
+}
+
+pub enum Status {
+    Info(String),
+    Error(String),
+}
+
+/// What the detail pane shows about a student, besides what `Student`
+/// has, gathered when it opens.
+pub struct Detail {
+    pub email: StudentKey,
+    /// Their attendance on each day it was taken, oldest first
+    pub attendance: Vec<attendance::Record>,
+    /// The points they earned in each of the class's sessions, oldest
+    /// first
+    pub points: Vec<u64>,
+    /// How far down their history is scrolled
+    pub scroll: u16,
+}
+
+/// Which of a student's notes and tags is being edited.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Notes,
+    Tags,
+}
+
+/// An edit of a student's notes or tags, in progress.
+pub struct Editing {
+    pub email: StudentKey,
+    pub field: Field,
+    pub text: String,
+    /// Position of the cursor, in characters
+    pub cursor: usize,
+}
+
+impl Editing {
+    fn byte_index(&self) -> usize {
+        self.text
+            .char_indices()
+            .map(|(i, _)| i)
+            .nth(self.cursor)
+            .unwrap_or(self.text.len())
+    }
+
+    pub fn enter_char(&mut self, c: char) {
+        let i = self.byte_index();
+        self.text.insert(i, c);
+        self.cursor += 1;
+    }
+
+    pub fn delete_char(&mut self) {
+        if self.cursor == 0 {
+            return;
+        }
+        self.cursor -= 1;
+        let i = self.byte_index();
+        self.text.remove(i);
+    }
+
+    pub fn move_cursor_left(&mut self) {
+        self.cursor = self.cursor.saturating_sub(1);
+    }
+
+    pub fn move_cursor_right(&mut self) {
+        self.cursor = (self.cursor + 1).min(self.text.chars().count());
+    }
+}
+
+/// A recorded outcome, along with the order of students on either
+/// side of it so that undo and redo can restore the list exactly.
+struct Action {
+    event: events::Event,
+    order_before: Vec<StudentKey>,
+    order_after: Vec<StudentKey>,
+}
+
+impl App {
+    pub fn new(sections: Vec<Section>) -> Self {
+        assert!(!sections.is_empty(), "an app needs at least one section");
+        App {
+            sections,
+            current: 0,
+            quitting: false,
+        }
+    }
+
+    pub fn next_section(&mut self) {
+        self.current = (self.current + 1) % self.sections.len();
+    }
+
+    pub fn previous_section(&mut self) {
+        self.current = (self.current + self.sections.len() - 1) % self.sections.len();
+    }
+
+    /// Has anything been logged in any section?
+    pub fn dirty(&self) -> bool {
+        self.sections.iter().any(|s| s.dirty)
+    }
+
+    pub fn input_mode(&self) -> InputMode {
+        if self.quitting {
+            return InputMode::Quitting;
+        }
+        self.sections[self.current].input_mode()
+    }
+
+    // Commit every section that has something to commit. On a failure,
+    // that section is brought up so its error shows, and the rest are
+    // left for another try.
+    pub fn commit(&mut self) -> bool {
+        for (i, s) in self.sections.iter_mut().enumerate() {
+            if s.dirty && !s.commit() {
+                self.current = i;
+                return false;
+            }
+            s.dirty = false;
+        }
+        true
+    }
+}
+
+impl Deref for App {
+    type Target = Section;
+
+    fn deref(&self) -> &Section {
+        &self.sections[self.current]
+    }
+}
+
+impl DerefMut for App {
+    fn deref_mut(&mut self) -> &mut Section {
+        &mut self.sections[self.current]
+    }
+}
+
+impl Section {
+    pub fn new(name: String, store: Box<dyn Store>, options: &Options) -> anyhow::Result<Self> {
+        let session = events::new_session();
+        let mut students = store::current(store.as_ref(), &session)?;
+        for e in store.history()? {
+            if let Some(s) = students.get_mut(&e.email) {
+                s.history.push(e);
+            }
+        }
+
+        let mut s = Self {
+            name,
+            store,
+            input: String::new(),
+            display_mode: DisplayMode::Command,
+            student_display: None,
+            detail: None,
+            editing: None,
+            students,
+            character_index: 0,
+            selection: None,
+            view: Vec::new(),
+            order: Vec::new(),
+            strategy: selection::by_name(&options.strategy)?,
+            configured_strategy: options.strategy.clone(),
+            cooldown: options.cooldown,
+            cooling: HashSet::new(),
+            outcomes: options.outcomes.clone(),
+            warned: HashSet::new(),
+            heads_up: None,
+            rng: options
+                .seed
+                .map_or_else(StdRng::from_entropy, StdRng::seed_from_u64),
+            session,
+            undo: Vec::new(),
+            redo: Vec::new(),
+            status: None,
+            dirty: false,
+            roll: Vec::new(),
+            roll_call: None,
+        };
+        // Pick up any attendance already taken today, and otherwise
+        // start class with the roll call.
+        let today = attendance::today();
+        let mut taken = false;
+        for r in s.store.attendance()? {
+            if r.date != today {
+                continue;
+            }
+            if let Some(st) = s.students.get_mut(&r.email) {
+                st.attendance = Some(r.status);
+                taken = true;
+            }
+        }
+        if !taken && s.students.values().any(|st| !st.inactive) {
+            s.start_roll_call();
+        }
+
+        for (email, r) in notes::latest(s.store.notes()?) {
+            if let Some(st) = s.students.get_mut(&email) {
+                st.notes = r.notes;
+                st.tags = notes::parse_tags(&r.tags);
+            }
+        }
+
+        s.randomize();
+        Ok(s)
+    }
+
+    pub fn move_cursor_left(&mut self) {
+        let cursor_moved_left = self.character_index.saturating_sub(1);
+        self.character_index = self.clamp_cursor(cursor_moved_left);
+    }
+
+    pub fn move_cursor_right(&mut self) {
+        let cursor_moved_right = self.character_index.saturating_add(1);
+        self.character_index = self.clamp_cursor(cursor_moved_right);
+    }
+
+    pub fn move_selection_up(&mut self) {
+        if self.selection.is_none() {
+            return;
+        }
+        let sel = self.selection.unwrap();
+        if sel > 0 {
+            self.selection = Some(sel - 1);
+        }
+    }
+
+    pub fn move_selection_down(&mut self) {
+        if self.selection.is_none() {
+            return;
+        }
+        let sel = self.selection.unwrap();
+        if sel < self.view.len() - 1 {
+            self.selection = Some(sel + 1);
+        }
+    }
+
+    fn selection_reset(&mut self) {
+        if !self.view.is_empty() {
+            self.selection = Some(0);
+        } else {
+            self.selection = None;
+        }
+    }
+
+    pub fn students_view(&self) -> &Vec<StudentKey> {
+        &self.view
+    }
+
+    pub fn selected_student(&self) -> Option<&Student> {
+        let sel = self.selection?;
+        assert!(self.view.len() >= sel);
+        Some(
+            self.students
+                .get(&self.view[sel])
+                .expect("View has a stale student not in the student db."),
+        )
+    }
+
+    fn update_student_view(&mut self) {
+        let view = if !self.input.is_empty() {
+            // If there's an active search term, use fuzzy matching on
+            // names. `#tag` finds students by their tags instead, and
+            // anything in a student's notes finds them too, after the
+            // names that match.
+            let matcher = SkimMatcherV2::default();
+            let query = self.input.to_lowercase();
+            let mut matched: Vec<(Student, i64)> = self
+                .students
+                .values()
+                .filter(|entry| !entry.inactive)
+                .filter_map(|entry| {
+                    if let Some(tag) = query.strip_prefix('#') {
+                        return entry
+                            .tags
+                            .iter()
+                            .any(|t| t.to_lowercase().starts_with(tag))
+                            .then(|| (entry.clone(), 0));
+                    }
+                    matcher
+                        .fuzzy_match(&entry.name.to_lowercase(), &query)
+                        .or_else(|| entry.notes.to_lowercase().contains(&query).then_some(0))
+                        .map(|score| (entry.clone(), score))
+                })
+                .collect();
+            matched.sort_by(|(_, a), (_, b)| b.cmp(a));
+            matched.into_iter().map(|(s, _)| s.email).collect()
+        } else {
+            // Otherwise just the order is random, biased by
+            // participation score, see `randomize` below.
+            self.order
+                .iter()
+                .map(|key| {
+                    self.students
+                        .get(key)
+                        .expect("The order is inconsistent and has a student not in the db.")
+                        .email
+                        .clone()
+                })
+                .collect()
+        };
+
+        self.view = view;
+        self.selection_reset();
+    }
+
+    pub fn enter_char(&mut self, new_char: char) {
+        let index = self.byte_index();
+        self.input.insert(index, new_char);
+        self.move_cursor_right();
+        self.update_student_view();
+    }
+
+    /// Returns the byte index based on the character position.
+    ///
+    /// Since each character in a string can be contain multiple bytes, it's necessary to calculate
+    /// the byte index based on the index of the character.
+    fn byte_index(&self) -> usize {
+        self.input
+            .char_indices()
+            .map(|(i, _)| i)
+            .nth(self.character_index)
+            .unwrap_or(self.input.len())
+    }
+
+    pub fn delete_char(&mut self) {
+        let is_not_cursor_leftmost = self.character_index != 0;
+        if is_not_cursor_leftmost {
+            // Method "remove" is not used on the saved text for deleting the selected char.
+            // Reason: Using remove on String works on bytes instead of the chars.
+            // Using remove would require special care because of char boundaries.
+
+            let current_index = self.character_index;
+            let from_left_to_current_index = current_index - 1;
+
+            // Getting all characters before the selected character.
+            let before_char_to_delete = self.input.chars().take(from_left_to_current_index);
+            // Getting all characters after selected character.
+            let after_char_to_delete = self.input.chars().skip(current_index);
+
+            // Put all characters together except the selected one.
+            // By leaving the selected one out, it is forgotten and therefore deleted.
+            self.input = before_char_to_delete.chain(after_char_to_delete).collect();
+            self.move_cursor_left();
+        }
+        self.update_student_view();
+    }
+
+    fn clamp_cursor(&self, new_cursor_pos: usize) -> usize {
+        new_cursor_pos.clamp(0, self.input.chars().count())
+    }
+
+    fn reset_cursor(&mut self) {
+        self.character_index = 0;
+    }
+
+    pub fn display_selected_student(&mut self) {
+        self.student_display = self.selected_student().cloned();
+    }
+
+    pub fn input_clear(&mut self) {
+        self.input.clear();
+        self.reset_cursor();
+        self.update_student_view();
+    }
+
+    pub fn input_mode(&self) -> InputMode {
+        if self.roll_call.is_some() {
+            return InputMode::RollCall;
+        }
+        if self.student_display.is_some() {
+            return InputMode::Student;
+        }
+        if self.editing.is_some() {
+            return InputMode::Editing;
+        }
+        if self.detail.is_some() {
+            return InputMode::Detail;
+        }
+        match self.display_mode {
+            DisplayMode::Command => InputMode::Command,
+            DisplayMode::Searching => InputMode::Searching,
+        }
+    }
+
+    pub fn student_escape(&mut self) {
+        self.student_display = None;
+    }
+
+    // Opens the detail view. The store is only read from, so that
+    // looking can't change anything recorded.
+    pub fn display_detail(&mut self) {
+        let Some(s) = self.selected_student() else {
+            return;
+        };
+        let email = s.email.clone();
+
+        let mut attendance: Vec<attendance::Record> = match self.store.attendance() {
+            Ok(records) => records.into_iter().filter(|r| r.email == email).collect(),
+            Err(e) => {
+                self.status = Some(Status::Error(format!("Could not read attendance: {e}")));
+                Vec::new()
+            }
+        };
+        // Marking a day again replaces what was marked before.
+        attendance.reverse();
+        let mut seen = HashSet::new();
+        attendance.retain(|r| seen.insert(r.date.clone()));
+        attendance.reverse();
+
+        // Every session anyone was called on in, in the order they were.
+        let mut sessions: Vec<(chrono::DateTime<chrono::Local>, &str)> = Vec::new();
+        for e in self.students.values().flat_map(|s| &s.history) {
+            match sessions.iter_mut().find(|(_, id)| *id == e.session) {
+                Some((start, _)) => *start = (*start).min(e.timestamp),
+                None => sessions.push((e.timestamp, &e.session)),
+            }
+        }
+        sessions.sort();
+        let history = &self.students[&email].history;
+        let points = sessions
+            .iter()
+            .map(|(_, id)| {
+                history
+                    .iter()
+                    .filter(|e| e.session == *id && e.counter == Counter::ParticipationScore)
+                    .map(events::Event::counted)
+                    .sum::<isize>()
+                    .max(0) as u64
+            })
+            .collect();
+
+        self.detail = Some(Detail {
+            email,
+            attendance,
+            points,
+            scroll: 0,
+        });
+    }
+
+    pub fn detail_scroll_down(&mut self) {
+        if let Some(d) = &mut self.detail {
+            d.scroll = d.scroll.saturating_add(1);
+        }
+    }
+
+    pub fn detail_scroll_up(&mut self) {
+        if let Some(d) = &mut self.detail {
+            d.scroll = d.scroll.saturating_sub(1);
+        }
+    }
+
+    pub fn detail_escape(&mut self) {
+        self.detail = None;
+    }
+
+    /// Start editing the notes or tags of the student in the detail
+    /// pane.
+    pub fn edit(&mut self, field: Field) {
+        let Some(d) = &self.detail else {
+            return;
+        };
+        let s = &self.students[&d.email];
+        let text = match field {
+            Field::Notes => s.notes.clone(),
+            Field::Tags => s.tags.join(" "),
+        };
+        self.editing = Some(Editing {
+            email: s.email.clone(),
+            field,
+            cursor: text.chars().count(),
+            text,
+        });
+    }
+
+    // Notes are recorded as soon as they are saved, apart from the
+    // session, so there is nothing to commit or undo.
+    pub fn edit_save(&mut self) {
+        let Some(e) = self.editing.take() else {
+            return;
+        };
+        let s = self
+            .students
+            .get_mut(&e.email)
+            .expect("Editing a student not in the db.");
+        let (notes, tags) = match e.field {
+            Field::Notes => (e.text.trim().to_string(), s.tags.clone()),
+            Field::Tags => (s.notes.clone(), notes::parse_tags(&e.text)),
+        };
+        let record = notes::Record {
+            timestamp: chrono::Local::now(),
+            email: e.email,
+            notes,
+            tags: tags.join(" "),
+        };
+        if let Err(err) = self.store.record_notes(&record) {
+            self.status = Some(Status::Error(format!("Could not save notes: {err}")));
+            return;
+        }
+        s.notes = record.notes;
+        s.tags = tags;
+        self.status = Some(Status::Info(format!("Saved notes for {}", s.name)));
+    }
+
+    pub fn edit_escape(&mut self) {
+        self.editing = None;
+    }
+
+    // Log the outcome for the active student first, and only then
+    // update the counters from that same event so that the roster can
+    // always be rebuilt from the log.
+    pub fn student_record(&mut self, outcome: &Outcome) {
+        assert!(self.student_display.is_some());
+        let s = self.student_display.as_ref().unwrap();
+
+        let event = outcome.event(&self.session, &s.email);
+        // Without the event in the log, nothing has happened.
+        if let Err(e) = self.store.append(&event) {
+            self.status = Some(Status::Error(format!(
+                "Could not log {}: {e}",
+                outcome.name
+            )));
+            self.student_escape();
+            return;
+        }
+        self.dirty = true;
+
+        let s = self
+            .students
+            .get_mut(&event.email)
+            .expect("Student database became inconsistent with active student");
+        s.apply(&event, &self.session);
+        s.history.push(event.clone());
+        self.status = Some(Status::Info(format!(
+            "Recorded {} for {}",
+            outcome.name, s.name
+        )));
+        // The next time they are up, they get another heads up.
+        self.warned.remove(&event.email);
+
+        let order_before = self.order.clone();
+        self.update_data();
+        self.undo.push(Action {
+            event,
+            order_before,
+            order_after: self.order.clone(),
+        });
+        // A new action forks history; what was undone is gone.
+        self.redo.clear();
+        self.student_escape();
+    }
+
+    // Undo is itself logged, as an event that reverses the original,
+    // so the log stays append-only and replays to the same counters.
+    pub fn undo(&mut self) {
+        let Some(action) = self.undo.pop() else {
+            self.status = Some(Status::Info("Nothing to undo".to_string()));
+            return;
+        };
+        match self.replay_action(&action, true) {
+            Ok(name) => {
+                self.status = Some(Status::Info(format!(
+                    "Undid {} for {}",
+                    action.event.outcome, name
+                )));
+                self.redo.push(action);
+            }
+            Err(e) => {
+                self.status = Some(Status::Error(format!("Could not log undo: {e}")));
+                self.undo.push(action);
+            }
+        }
+        self.persist();
+    }
+
+    pub fn redo(&mut self) {
+        let Some(action) = self.redo.pop() else {
+            self.status = Some(Status::Info("Nothing to redo".to_string()));
+            return;
+        };
+        match self.replay_action(&action, false) {
+            Ok(name) => {
+                self.status = Some(Status::Info(format!(
+                    "Redid {} for {}",
+                    action.event.outcome, name
+                )));
+                self.undo.push(action);
+            }
+            Err(e) => {
+                self.status = Some(Status::Error(format!("Could not log redo: {e}")));
+                self.redo.push(action);
+            }
+        }
+        self.persist();
+    }
+
+    // Log and apply the action's event (or its reversal), and restore
+    // the order from that side of the action. Returns the name of the
+    // student involved.
+    fn replay_action(&mut self, action: &Action, undo: bool) -> anyhow::Result<String> {
+        let event = events::Event {
+            timestamp: chrono::Local::now(),
+            undo,
+            ..action.event.clone()
+        };
+        self.store.append(&event)?;
+        self.dirty = true;
+
+        let s = self
+            .students
+            .get_mut(&event.email)
+            .expect("Student database became inconsistent with the undo history");
+        s.apply(&event, &self.session);
+        s.history.push(event.clone());
+        let name = s.name.clone();
+
+        self.order = if undo {
+            action.order_before.clone()
+        } else {
+            action.order_after.clone()
+        };
+        self.heads_up = None;
+        self.cool(chrono::Local::now());
+        self.colorize();
+        self.update_student_view();
+
+        Ok(name)
+    }
+
+    // Each draw gets its own seed, which is recorded along with who
+    // could be called on and who had had their heads up, so that the
+    // order can be replayed.
+    pub fn randomize(&mut self) {
+        let seed = self.rng.gen();
+        let now = chrono::Local::now();
+        // Students who aren't in the room today can't be called on.
+        let (present, absent): (Vec<&Student>, Vec<&Student>) =
+            self.students.values().partition(|s| s.in_room());
+        let mut excluded: Vec<&str> = absent.iter().map(|s| s.email.as_str()).collect();
+        excluded.sort();
+        let excluded = excluded.join(" ");
+        let mut warned: Vec<&str> = self.warned.iter().map(|k| k.as_str()).collect();
+        warned.sort();
+        let order = selection::draw(self.strategy.as_mut(), present.into_iter(), seed);
+
+        let draw = Draw {
+            timestamp: now,
+            session: self.session.clone(),
+            strategy: self.strategy.spec(),
+            seed,
+            excluded,
+            cooldown: self.cooldown.map(|c| c.to_string()).unwrap_or_default(),
+            warned: warned.join(" "),
+        };
+        if let Err(e) = self.store.record_draw(&draw) {
+            self.status = Some(Status::Error(format!("Could not record the draw: {e}")));
+        }
+
+        self.cool(now);
+        let order = cooldown::demote(order, &self.cooling);
+        let order = calling::demote(order, &self.students);
+        (self.order, self.heads_up) =
+            calling::warn(order, &self.students, &self.cooling, &mut self.warned);
+        self.colorize();
+        self.update_student_view();
+        self.selection_reset();
+    }
+
+    fn cool(&mut self, now: chrono::DateTime<chrono::Local>) {
+        self.cooling = match self.cooldown {
+            Some(c) => cooldown::cooling(self.students.values(), &self.session, now, c),
+            None => HashSet::new(),
+        };
+    }
+
+    // Calculate the colors by separating students into fifths of
+    // the (participation - deferral) score.
+    fn colorize(&mut self) {
+        let mut score_ordered: Vec<(StudentKey, isize)> = self
+            .students
+            .values()
+            .map(|s| (s.email.clone(), s.score()))
+            .collect();
+        // sort by the computed score
+        score_ordered.sort_by_key(|(_, p0)| *p0);
+        for (_, s) in self.students.iter_mut() {
+            // Get the student's index, sorted by score.
+            let (i, p) = score_ordered
+                .iter()
+                .enumerate()
+                .find_map(
+                    |(i, (e, p))| {
+                        if s.email == *e {
+                            Some((i, p))
+                        } else {
+                            None
+                        }
+                    },
+                )
+                .expect("Could not find student when an entry must exist.");
+
+            let quintile_sz: usize =
+                (score_ordered.len() as f64 / NUM_COLORS as f64).ceil() as usize;
+            let quintile: usize = i / quintile_sz;
+            assert!(quintile < NUM_COLORS);
+            let mut final_quintile = quintile;
+            // this is odd, but necessary: if this student has the
+            // same score as the last student in the previous
+            // quintile, inherit the previous quitile. This must be
+            // done recursively for all previous quintiles to handle
+            // the case that all students that all students have the
+            // same score.
+            for q in (1..=quintile).rev() {
+                let prev_quint_score = score_ordered[(q * quintile_sz) - 1].1;
+
+                // The last element of the previous quintile has a
+                // different score, so we know we belong in our
+                // quintile
+                if *p != prev_quint_score {
+                    break;
+                }
+                final_quintile = q - 1;
+            }
+            s.color = final_quintile;
+            assert!(s.color < NUM_COLORS);
+        }
+    }
+
+    pub fn next_strategy(&mut self) {
+        self.strategy = selection::next(self.strategy.name(), &self.configured_strategy);
+        self.status = Some(Status::Info(format!(
+            "Selecting students by {}",
+            self.strategy.spec()
+        )));
+        self.randomize();
+    }
+
+    // With nobody enrolled, e.g. in a new roster, there is no one to
+    // call the roll for.
+    pub fn start_roll_call(&mut self) {
+        let mut roll: Vec<&Student> = self.students.values().filter(|s| !s.inactive).collect();
+        roll.sort_by(|a, b| a.name.cmp(&b.name));
+        self.roll = roll.into_iter().map(|s| s.email.clone()).collect();
+        if self.roll.is_empty() {
+            self.status = Some(Status::Info(
+                "Nobody is enrolled to take attendance for".to_string(),
+            ));
+            return;
+        }
+        self.roll_call = Some(0);
+    }
+
+    pub fn roll_call_student(&self) -> Option<&Student> {
+        let i = self.roll_call?;
+        self.students.get(self.roll.get(i)?)
+    }
+
+    // Mark the current student in the roll call and move on to the
+    // next, finishing after the last.
+    pub fn mark_attendance(&mut self, status: Attendance) {
+        let Some((i, email)) = self.roll_call.and_then(|i| Some((i, self.roll.get(i)?))) else {
+            return;
+        };
+        let record = attendance::Record {
+            date: attendance::today(),
+            email: email.clone(),
+            status,
+        };
+        if let Err(e) = self.store.record_attendance(&record) {
+            self.status = Some(Status::Error(format!("Could not record attendance: {e}")));
+            return;
+        }
+        if let Some(s) = self.students.get_mut(&record.email) {
+            s.attendance = Some(status);
+        }
+
+        if i + 1 < self.roll.len() {
+            self.roll_call = Some(i + 1);
+        } else {
+            self.roll_call_escape();
+        }
+    }
+
+    pub fn roll_call_back(&mut self) {
+        if let Some(i) = self.roll_call {
+            self.roll_call = Some(i.saturating_sub(1));
+        }
+    }
+
+    // Absent students drop out of the order, so it needs redoing.
+    pub fn roll_call_escape(&mut self) {
+        self.roll_call = None;
+        self.randomize();
+    }
+
+    // Make this session's output the new roster. Returns whether it
+    // worked, so the caller knows if it is safe to quit.
+    pub fn commit(&mut self) -> bool {
+        if let Err(e) = self
+            .store
+            .save(&self.students)
+            .and_then(|_| self.store.commit())
+        {
+            self.status = Some(Status::Error(format!("Could not commit: {e}")));
+            return false;
+        }
+        true
+    }
+
+    // The data has been updated, so we need to update all
+    // corresponding data-structures, and the db.
+    fn update_data(&mut self) {
+        self.randomize();
+        self.persist();
+    }
+
+    // Write out the roster, reporting a failure on the status line.
+    // The event is already in the log, so nothing is lost, and the
+    // next successful write catches the file up.
+    fn persist(&mut self) {
+        if let Err(e) = self.store.save(&self.students) {
+            self.status = Some(Status::Error(format!("Could not save the roster: {e}")));
+        }
+    }
+}
@@ -0,0 +1,65 @@
+// Who was in the room, per date. This is separate from the `absent`
+// outcome, which is about not answering when called on.
+
+use std::{fmt, str::FromStr};
+
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+
+use crate::StudentKey;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Attendance {
+    Present,
+    Absent,
+    Late,
+    Excused,
+}
+
+impl Attendance {
+    /// Can this student be called on?
+    pub fn in_room(&self) -> bool {
+        matches!(self, Attendance::Present | Attendance::Late)
+    }
+}
+
+impl fmt::Display for Attendance {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            Attendance::Present => "present",
+            Attendance::Absent => "absent",
+            Attendance::Late => "late",
+            Attendance::Excused => "excused",
+        };
+        write!(f, "{s}")
+    }
+}
+
+impl FromStr for Attendance {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "present" => Ok(Attendance::Present),
+            "absent" => Ok(Attendance::Absent),
+            "late" => Ok(Attendance::Late),
+            "excused" => Ok(Attendance::Excused),
+            _ => anyhow::bail!("Unknown attendance \"{s}\"."),
+        }
+    }
+}
+
+/// A student's attendance on a date. Marking a student again on the
+/// same date replaces the earlier record.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Record {
+    pub date: String,
+    pub email: StudentKey,
+    pub status: Attendance,
+}
+
+/// Attendance is kept per calendar day.
+pub fn today() -> String {
+    Local::now().format("%Y-%m-%d").to_string()
+}
@@ -0,0 +1,114 @@
+// Accommodations in how a student is called on, kept in the roster.
+// Students who are exempt from cold-calling, or who only answer when
+// they volunteer, are still ordered by the strategy, so they can be
+// picked by hand, but never come up at the top. A student who wants a
+// warning first gets a heads up while someone else is called on, and
+// only then is moved to the top.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    str::FromStr,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Student, StudentKey};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub enum Calling {
+    /// Called on like anyone else
+    #[default]
+    #[serde(rename = "", alias = "normal")]
+    Normal,
+    /// Never cold-called
+    #[serde(rename = "exempt")]
+    Exempt,
+    /// Only called on when they volunteer
+    #[serde(rename = "volunteer")]
+    Volunteer,
+    /// Given a heads up before being called on
+    #[serde(rename = "warn")]
+    Warn,
+}
+
+impl Calling {
+    /// Can they come up at the top of the order?
+    pub fn cold_called(self) -> bool {
+        !matches!(self, Calling::Exempt | Calling::Volunteer)
+    }
+}
+
+impl fmt::Display for Calling {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            Calling::Normal => "normal",
+            Calling::Exempt => "exempt",
+            Calling::Volunteer => "volunteer",
+            Calling::Warn => "warn",
+        };
+        write!(f, "{s}")
+    }
+}
+
+impl FromStr for Calling {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "" | "normal" => Ok(Calling::Normal),
+            "exempt" => Ok(Calling::Exempt),
+            "volunteer" => Ok(Calling::Volunteer),
+            "warn" => Ok(Calling::Warn),
+            _ => anyhow::bail!(
+                "Unknown calling \"{s}\", expected normal, exempt, volunteer, or warn."
+            ),
+        }
+    }
+}
+
+/// `order`, with whoever isn't cold-called moved to the bottom, in the
+/// order they were in.
+pub fn demote(order: Vec<StudentKey>, students: &HashMap<StudentKey, Student>) -> Vec<StudentKey> {
+    let (cold, not): (Vec<StudentKey>, Vec<StudentKey>) = order
+        .into_iter()
+        .partition(|k| students.get(k).is_none_or(|s| s.calling.cold_called()));
+    cold.into_iter().chain(not).collect()
+}
+
+/// `order`, with anyone in `warned` moved to the top, since they have
+/// had their heads up. If that leaves a student who wants a warning at
+/// the top without one, they are warned instead: added to `warned`,
+/// and moved down below the next student, unless that student is
+/// `cooling` or not cold-called. Returns the new order, and who was
+/// just warned.
+pub fn warn(
+    order: Vec<StudentKey>,
+    students: &HashMap<StudentKey, Student>,
+    cooling: &HashSet<StudentKey>,
+    warned: &mut HashSet<StudentKey>,
+) -> (Vec<StudentKey>, Option<StudentKey>) {
+    let (ready, rest): (Vec<StudentKey>, Vec<StudentKey>) =
+        order.into_iter().partition(|k| warned.contains(k));
+    let mut order: Vec<StudentKey> = ready.into_iter().chain(rest).collect();
+
+    let Some(top) = order.first() else {
+        return (order, None);
+    };
+    let wants_warning = students
+        .get(top)
+        .is_some_and(|s| s.calling == Calling::Warn);
+    if !wants_warning || warned.contains(top) {
+        return (order, None);
+    }
+    let top = top.clone();
+    warned.insert(top.clone());
+    // With nobody else to call on first, the heads up will have to do.
+    let next_up = order.get(1).is_some_and(|k| {
+        !cooling.contains(k) && students.get(k).is_some_and(|s| s.calling.cold_called())
+    });
+    if next_up {
+        order.swap(0, 1);
+    }
+    (order, Some(top))
+}
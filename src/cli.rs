@@ -0,0 +1,215 @@
+// Subcommands that do one thing and exit, for shell scripts and editor
+// integrations. They work on the same store as the TUI: `pick` draws
+// just as randomizing in class does, and `record` logs an event and
+// writes out the totals, so a later commit picks it up. Each returns
+// what it did, for the binary to print.
+
+use std::{collections::HashMap, io, path::Path};
+
+use chrono::{DateTime, Local};
+
+use crate::{
+    app::{Options, Section, Status},
+    attendance::{self, Attendance},
+    calling::Calling,
+    events::{self, Outcome},
+    grade::{self, Formula},
+    import::{self, Mapping, Summary},
+    notes,
+    store::{self, Store},
+    sync::{self, Change},
+    Student, StudentKey,
+};
+
+/// The first `n` students of a fresh draw.
+pub fn pick(store: Box<dyn Store>, options: &Options, n: usize) -> anyhow::Result<Vec<Student>> {
+    let app = Section::new(String::new(), store, options)?;
+    if let Some(Status::Error(e)) = &app.status {
+        anyhow::bail!("{e}");
+    }
+    Ok(app
+        .students_view()
+        .iter()
+        .take(n)
+        .map(|k| app.students[k].clone())
+        .collect())
+}
+
+/// Log the outcome named `outcome`, one of `outcomes`, for the student
+/// with `email`, in a session of its own. Returns the student, with
+/// the outcome counted.
+pub fn record(
+    store: &mut dyn Store,
+    email: &str,
+    outcome: &str,
+    outcomes: &[Outcome],
+) -> anyhow::Result<Student> {
+    let Some(outcome) = outcomes.iter().find(|o| o.name == outcome) else {
+        let names: Vec<&str> = outcomes.iter().map(|o| o.name.as_str()).collect();
+        anyhow::bail!(
+            "Unknown outcome \"{outcome}\", expected one of {}.",
+            names.join(", ")
+        );
+    };
+    let session = events::new_session();
+    let mut students = store::current(store, &session)?;
+    let Some(s) = students.get_mut(email) else {
+        anyhow::bail!("There is no student {email} in the roster.");
+    };
+    if s.inactive {
+        anyhow::bail!("{} has dropped the course.", s.name);
+    }
+
+    let event = outcome.event(&session, &s.email);
+    store.append(&event)?;
+    s.apply(&event, &session);
+    let recorded = s.clone();
+
+    store.save(&students)?;
+    Ok(recorded)
+}
+
+/// Set how the student with `email` is called on. Returns the student.
+pub fn set_calling(
+    store: &mut dyn Store,
+    email: &str,
+    calling: Calling,
+) -> anyhow::Result<Student> {
+    let mut roster = store.load()?;
+    let Some(s) = roster.get_mut(email) else {
+        anyhow::bail!("There is no student {email} in the roster.");
+    };
+    s.calling = calling;
+    let set = s.clone();
+    write_roster(store, &roster)?;
+    Ok(set)
+}
+
+/// Everything there is to know about a student.
+#[derive(Debug, Clone)]
+pub struct Profile {
+    /// With their counters as of now, and their whole history
+    pub student: Student,
+    /// Their attendance today, if taken
+    pub today: Option<Attendance>,
+    pub last_called: Option<DateTime<Local>>,
+    /// The instructor's latest notes and tags on them
+    pub notes: Option<notes::Record>,
+}
+
+/// Everything there is to know about the student with `email`.
+pub fn show(store: &dyn Store, email: &str) -> anyhow::Result<Profile> {
+    let mut students = store::current(store, "")?;
+    let Some(mut student) = students.remove(email) else {
+        anyhow::bail!("There is no student {email} in the roster.");
+    };
+    let today = attendance::today();
+    let today = store
+        .attendance()?
+        .into_iter()
+        .rfind(|r| r.date == today && r.email == email)
+        .map(|r| r.status);
+    student.history = store
+        .history()?
+        .into_iter()
+        .filter(|e| e.email == email)
+        .collect();
+
+    Ok(Profile {
+        today,
+        last_called: student.last_called(),
+        notes: notes::latest(store.notes()?).remove(email),
+        student,
+    })
+}
+
+/// The current roster, sorted by `name`, `email`, or `score` (lowest
+/// first). The score is the one students are picked by, so it goes by
+/// their history too.
+pub fn list(store: &dyn Store, sort: &str) -> anyhow::Result<Vec<Student>> {
+    let mut students = store::current(store, "")?;
+    for e in store.history()? {
+        if let Some(s) = students.get_mut(&e.email) {
+            s.history.push(e);
+        }
+    }
+    let mut students: Vec<Student> = students.into_values().collect();
+    match sort {
+        "name" => students.sort_by(|a, b| a.name.cmp(&b.name)),
+        "email" => students.sort_by(|a, b| a.email.cmp(&b.email)),
+        "score" => students.sort_by(|a, b| a.score().cmp(&b.score()).then(a.name.cmp(&b.name))),
+        _ => anyhow::bail!("Unknown sort \"{sort}\", expected name, email, or score."),
+    }
+    Ok(students)
+}
+
+/// Merge the students in the LMS export at `path` into the roster, or
+/// into a new one if there is no roster yet.
+pub fn import(
+    store: &mut dyn Store,
+    fresh: bool,
+    path: &Path,
+    mapping: &Mapping,
+) -> anyhow::Result<Summary> {
+    let imported = import::read(path, mapping)?;
+    if imported.is_empty() {
+        anyhow::bail!("There are no students in {}.", path.display());
+    }
+    let mut roster = if fresh { HashMap::new() } else { store.load()? };
+    let summary = import::merge(&mut roster, imported);
+    write_roster(store, &roster)?;
+    Ok(summary)
+}
+
+/// The changes that would bring the roster in line with the enrollment
+/// list at `path`, for `sync` to make those the user agrees to.
+pub fn enrollment_changes(
+    store: &dyn Store,
+    path: &Path,
+    mapping: &Mapping,
+) -> anyhow::Result<Vec<Change>> {
+    let enrolled = import::read(path, mapping)?;
+    if enrolled.is_empty() {
+        // Most likely the wrong file, rather than everyone dropping.
+        anyhow::bail!("There are no students in {}.", path.display());
+    }
+    Ok(sync::diff(&store.load()?, enrolled))
+}
+
+/// Make `changes` to the roster.
+pub fn sync(store: &mut dyn Store, changes: Vec<Change>) -> anyhow::Result<()> {
+    if changes.is_empty() {
+        return Ok(());
+    }
+    let mut roster = store.load()?;
+    for change in changes {
+        sync::apply(&mut roster, change);
+    }
+    write_roster(store, &roster)
+}
+
+// Replace the roster. Output from a session yet to be committed has to
+// take in the changes too, or it would no longer match the roster.
+fn write_roster(
+    store: &mut dyn Store,
+    roster: &HashMap<StudentKey, Student>,
+) -> anyhow::Result<()> {
+    store.write_roster(roster)?;
+    if !store.events()?.is_empty() {
+        store.save(&store::current(store, "")?)?;
+    }
+    Ok(())
+}
+
+/// Write everyone's grade by `formula` to `w`, as a gradebook upload in
+/// `format`.
+pub fn export(
+    w: impl io::Write,
+    store: &dyn Store,
+    formula: &Formula,
+    format: &str,
+    column: &str,
+) -> anyhow::Result<()> {
+    let students = store::current(store, "")?;
+    grade::write(w, &students, formula, format, column)
+}
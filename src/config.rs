@@ -0,0 +1,231 @@
+// Settings for a course. They are gathered from, in increasing order
+// of precedence, the user's `$XDG_CONFIG_HOME/participation/config.toml`,
+// the course's `participation.toml` next to the roster (or in the
+// current directory, when no roster is given), and the command line.
+// So each course's settings travel with its roster, and a roster named
+// in a config file needn't be given at all. Relative paths in a config
+// file are relative to the file.
+//
+// Several sections of a course can be opened at once by listing their
+// rosters as `sections`, which stands in for `roster`.
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+
+use crate::{app, cooldown::Cooldown, events, grade, import, store};
+
+/// The name of a course's config file, next to its roster.
+pub const COURSE: &str = "participation.toml";
+
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub roster: Option<PathBuf>,
+    /// Rosters to open together, one tab each, instead of `roster`
+    pub sections: Option<Vec<PathBuf>>,
+    pub output: Option<PathBuf>,
+    /// A single character, or `tab`
+    pub delimiter: Option<String>,
+    /// `csv` or `sqlite`
+    pub store: Option<String>,
+    pub strategy: Option<String>,
+    pub seed: Option<u64>,
+    /// A number of students, or of minutes, e.g. `10m`
+    pub cooldown: Option<String>,
+    pub theme: Option<String>,
+    /// Which columns of an LMS export to import
+    pub import: import::Mapping,
+    /// How participation becomes a grade, for exporting
+    pub grade: grade::Formula,
+    /// What students can be recorded with, in place of the defaults
+    pub outcomes: Option<Vec<events::Outcome>>,
+}
+
+impl Config {
+    pub fn read(path: &Path) -> anyhow::Result<Self> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Could not read {}: {e}", path.display()))?;
+        let mut config: Config = toml::from_str(&text)
+            .map_err(|e| anyhow::anyhow!("Could not parse {}: {e}", path.display()))?;
+
+        let dir = path.parent().unwrap_or(Path::new(""));
+        for p in [&mut config.roster, &mut config.output]
+            .into_iter()
+            .flatten()
+            .chain(config.sections.iter_mut().flatten())
+        {
+            if p.is_relative() {
+                *p = dir.join(&*p);
+            }
+        }
+        Ok(config)
+    }
+
+    /// `self`, with whatever `over` sets taking precedence.
+    /// A roster and a list of sections replace each other, since either
+    /// says what to open.
+    pub fn merge(self, over: Config) -> Config {
+        let (roster, sections) = if over.roster.is_some() || over.sections.is_some() {
+            (over.roster, over.sections)
+        } else {
+            (self.roster, self.sections)
+        };
+        Config {
+            roster,
+            sections,
+            output: over.output.or(self.output),
+            delimiter: over.delimiter.or(self.delimiter),
+            store: over.store.or(self.store),
+            strategy: over.strategy.or(self.strategy),
+            seed: over.seed.or(self.seed),
+            cooldown: over.cooldown.or(self.cooldown),
+            theme: over.theme.or(self.theme),
+            import: self.import.merge(over.import),
+            grade: self.grade.merge(over.grade),
+            outcomes: over.outcomes.or(self.outcomes),
+        }
+    }
+
+    /// The user's config file, whether or not it exists.
+    pub fn user_path() -> Option<PathBuf> {
+        let base = match env::var_os("XDG_CONFIG_HOME") {
+            Some(d) if !d.is_empty() => PathBuf::from(d),
+            _ => PathBuf::from(env::var_os("HOME")?).join(".config"),
+        };
+        Some(base.join("participation").join("config.toml"))
+    }
+
+    /// The settings from the command line, `given`, layered over the
+    /// config files. The course's config is `course` if given, and is
+    /// otherwise looked for next to the roster.
+    pub fn discover(given: Config, course: Option<&Path>) -> anyhow::Result<Config> {
+        let user = match Config::user_path() {
+            Some(p) if p.exists() => Config::read(&p)?,
+            _ => Config::default(),
+        };
+
+        let course = match course {
+            Some(p) => Config::read(p)?,
+            None => {
+                let first = |c: &Config| {
+                    c.roster
+                        .clone()
+                        .or_else(|| c.sections.as_ref()?.first().cloned())
+                };
+                let roster = first(&given).or_else(|| first(&user));
+                let dir = match roster.as_deref().and_then(Path::parent) {
+                    Some(d) if d != Path::new("") => d,
+                    _ => Path::new("."),
+                };
+                let path = dir.join(COURSE);
+                if path.exists() {
+                    Config::read(&path)?
+                } else {
+                    Config::default()
+                }
+            }
+        };
+
+        Ok(user.merge(course).merge(given))
+    }
+
+    pub fn roster(&self) -> anyhow::Result<&Path> {
+        if self.roster.is_none() && self.sections.is_some() {
+            anyhow::bail!("This works on one section at a time; give its roster with --roster.");
+        }
+        self.roster.as_deref().ok_or_else(|| {
+            anyhow::anyhow!(
+                "No roster given, and none is set in {COURSE} or {}.",
+                Config::user_path()
+                    .map(|p| p.display().to_string())
+                    .unwrap_or("the user's config".to_string())
+            )
+        })
+    }
+
+    /// Every roster to open, in the order of their tabs.
+    pub fn rosters(&self) -> anyhow::Result<Vec<&Path>> {
+        match &self.sections {
+            Some(sections) if !sections.is_empty() => {
+                if sections.len() > 1 && self.output.is_some() {
+                    anyhow::bail!(
+                        "An output file can only be given for a single roster; \
+                         each section writes next to its own."
+                    );
+                }
+                Ok(sections.iter().map(PathBuf::as_path).collect())
+            }
+            _ => Ok(vec![self.roster()?]),
+        }
+    }
+
+    pub fn store_options(&self) -> anyhow::Result<store::Options> {
+        let delimiter = match self.delimiter.as_deref() {
+            None | Some("tab" | "\\t") => b'\t',
+            Some(d) if d.len() == 1 && d.is_ascii() => d.as_bytes()[0],
+            Some(d) => {
+                anyhow::bail!("A delimiter must be a single character or \"tab\", not \"{d}\".")
+            }
+        };
+        Ok(store::Options {
+            kind: self.store.clone(),
+            output: self.output.clone(),
+            delimiter,
+        })
+    }
+
+    pub fn section_options(&self) -> anyhow::Result<app::Options> {
+        Ok(app::Options {
+            strategy: self.strategy().to_string(),
+            seed: self.seed,
+            cooldown: self.cooldown()?,
+            outcomes: self.outcomes()?,
+        })
+    }
+
+    pub fn strategy(&self) -> &str {
+        self.strategy.as_deref().unwrap_or("biased")
+    }
+
+    pub fn cooldown(&self) -> anyhow::Result<Option<Cooldown>> {
+        self.cooldown.as_deref().map(str::parse).transpose()
+    }
+
+    /// The course's outcomes, each with a name and a key of its own.
+    /// Escape is always for going back.
+    pub fn outcomes(&self) -> anyhow::Result<Vec<events::Outcome>> {
+        let Some(outcomes) = &self.outcomes else {
+            return Ok(events::default_outcomes());
+        };
+        if outcomes.is_empty() {
+            anyhow::bail!("At least one outcome is needed to record anything.");
+        }
+        for (i, o) in outcomes.iter().enumerate() {
+            if o.name.trim().is_empty() {
+                anyhow::bail!("Outcome {} has no name.", i + 1);
+            }
+            if !o.key.is_ascii_graphic() {
+                anyhow::bail!(
+                    "The key for \"{}\" must be a letter, digit, or punctuation.",
+                    o.name
+                );
+            }
+            if let Some(other) = outcomes[..i].iter().find(|other| other.name == o.name) {
+                anyhow::bail!("There are two outcomes named \"{}\".", other.name);
+            }
+            if let Some(other) = outcomes[..i].iter().find(|other| other.key == o.key) {
+                anyhow::bail!(
+                    "\"{}\" and \"{}\" are both recorded with {}.",
+                    other.name,
+                    o.name,
+                    o.key
+                );
+            }
+        }
+        Ok(outcomes.clone())
+    }
+}
@@ -0,0 +1,227 @@
+// Keeping a student who was just called from coming straight back up.
+// Whoever is cooling down still gets ordered by the strategy, but is
+// moved to the bottom of the list until enough other students have
+// been called, or enough time has passed.
+
+use std::{collections::HashSet, fmt, str::FromStr};
+
+use chrono::{DateTime, Local};
+
+use crate::{events::Event, Student, StudentKey};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Cooldown {
+    /// At least this many other students are called in between.
+    Students(usize),
+    /// At least this many minutes pass in between.
+    Minutes(f64),
+}
+
+impl FromStr for Cooldown {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let split = s.find(|c: char| c.is_alphabetic()).unwrap_or(s.len());
+        let (n, unit) = s.split_at(split);
+        let parsed = match unit {
+            "" | "students" => n.parse().ok().map(Cooldown::Students),
+            "m" | "min" | "minutes" => n
+                .parse()
+                .ok()
+                .filter(|m: &f64| *m >= 0.0)
+                .map(Cooldown::Minutes),
+            _ => None,
+        };
+        parsed.ok_or_else(|| {
+            anyhow::anyhow!("Unknown cooldown \"{s}\", expected e.g. 3 (students) or 10m.")
+        })
+    }
+}
+
+impl fmt::Display for Cooldown {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Cooldown::Students(n) => write!(f, "{n}"),
+            Cooldown::Minutes(m) => write!(f, "{m}m"),
+        }
+    }
+}
+
+/// The students who were called in `session`, as of `now`, too
+/// recently to be called again.
+pub fn cooling<'a>(
+    students: impl Iterator<Item = &'a Student>,
+    session: &str,
+    now: DateTime<Local>,
+    cooldown: Cooldown,
+) -> HashSet<StudentKey> {
+    let mut events: Vec<&Event> = students
+        .flat_map(|s| s.history.iter())
+        .filter(|e| e.session == session && e.timestamp <= now)
+        .collect();
+    events.sort_by_key(|e| e.timestamp);
+
+    // Who was called, in order, with any call that was undone taken
+    // back out again.
+    let mut calls: Vec<&Event> = Vec::new();
+    for e in events {
+        if e.undo {
+            if let Some(i) = calls
+                .iter()
+                .rposition(|c| c.email == e.email && c.outcome == e.outcome)
+            {
+                calls.remove(i);
+            }
+        } else {
+            calls.push(e);
+        }
+    }
+
+    let mut cooling = HashSet::new();
+    for (i, call) in calls.iter().enumerate() {
+        // Only their latest call counts.
+        if calls[i + 1..].iter().any(|c| c.email == call.email) {
+            continue;
+        }
+        let cool = match cooldown {
+            Cooldown::Students(n) => {
+                let since: HashSet<&str> =
+                    calls[i + 1..].iter().map(|c| c.email.as_str()).collect();
+                since.len() < n
+            }
+            Cooldown::Minutes(m) => ((now - call.timestamp).num_seconds() as f64) < m * 60.0,
+        };
+        if cool {
+            cooling.insert(call.email.clone());
+        }
+    }
+    cooling
+}
+
+/// `order`, with the students who are cooling down moved to the end,
+/// each part keeping the order it had.
+pub fn demote(order: Vec<StudentKey>, cooling: &HashSet<StudentKey>) -> Vec<StudentKey> {
+    let (cool, ready): (Vec<StudentKey>, Vec<StudentKey>) =
+        order.into_iter().partition(|k| cooling.contains(k));
+    ready.into_iter().chain(cool).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::Counter;
+
+    fn at(time: &str) -> DateTime<Local> {
+        format!("2026-09-01T{time}:00+00:00").parse().unwrap()
+    }
+
+    /// A class whose history is each of `calls`: who was called, in
+    /// which session, at what time, and whether it was an undo.
+    fn class(calls: &[(&str, &str, &str, bool)]) -> Vec<Student> {
+        let mut students: Vec<Student> = ["a", "b", "c"]
+            .map(|k| Student::new(k.to_string(), k.to_string()))
+            .into();
+        for &(email, session, time, undo) in calls {
+            let s = students.iter_mut().find(|s| s.email == email).unwrap();
+            s.history.push(Event {
+                timestamp: at(time),
+                session: session.to_string(),
+                email: email.to_string(),
+                outcome: "answer".to_string(),
+                undo,
+                counter: Counter::ParticipationScore,
+                points: 1,
+                weighted: true,
+            });
+        }
+        students
+    }
+
+    fn cooling_at(students: &[Student], time: &str, cooldown: Cooldown) -> Vec<StudentKey> {
+        let mut cooling: Vec<StudentKey> = cooling(students.iter(), "s", at(time), cooldown)
+            .into_iter()
+            .collect();
+        cooling.sort();
+        cooling
+    }
+
+    #[test]
+    fn students_cool_down_until_enough_others_are_called() {
+        let students = class(&[
+            ("a", "s", "10:00", false),
+            ("b", "s", "10:01", false),
+            ("a", "earlier", "10:02", false),
+            ("c", "s", "10:03", false),
+        ]);
+        assert_eq!(
+            cooling_at(&students, "10:01", Cooldown::Students(2)),
+            ["a", "b"]
+        );
+        // Calls in other sessions don't count.
+        assert_eq!(
+            cooling_at(&students, "10:02", Cooldown::Students(2)),
+            ["a", "b"]
+        );
+        assert_eq!(
+            cooling_at(&students, "10:03", Cooldown::Students(2)),
+            ["b", "c"]
+        );
+        assert_eq!(cooling_at(&students, "10:03", Cooldown::Students(1)), ["c"]);
+        assert!(cooling_at(&students, "10:03", Cooldown::Students(0)).is_empty());
+    }
+
+    #[test]
+    fn students_cool_down_for_minutes() {
+        let students = class(&[("a", "s", "10:00", false), ("b", "s", "10:08", false)]);
+        assert_eq!(
+            cooling_at(&students, "10:09", Cooldown::Minutes(10.0)),
+            ["a", "b"]
+        );
+        assert_eq!(
+            cooling_at(&students, "10:10", Cooldown::Minutes(10.0)),
+            ["b"]
+        );
+        assert_eq!(
+            cooling_at(&students, "10:10", Cooldown::Minutes(1.5)),
+            Vec::<String>::new()
+        );
+        // Nothing after `now` counts, as in a replay.
+        assert!(cooling_at(&students, "09:59", Cooldown::Minutes(10.0)).is_empty());
+    }
+
+    #[test]
+    fn an_undone_call_is_taken_back() {
+        let students = class(&[
+            ("a", "s", "10:00", false),
+            ("b", "s", "10:01", false),
+            ("b", "s", "10:02", true),
+        ]);
+        // With b's call undone, nobody has been called since a.
+        assert_eq!(cooling_at(&students, "10:02", Cooldown::Students(1)), ["a"]);
+        assert_eq!(
+            cooling_at(&students, "10:02", Cooldown::Minutes(10.0)),
+            ["a"]
+        );
+        assert_eq!(cooling_at(&students, "10:01", Cooldown::Students(1)), ["b"]);
+    }
+
+    #[test]
+    fn cooling_students_go_last_in_their_order() {
+        let order = ["c", "a", "d", "b"].map(String::from).into();
+        let cooling = ["a", "c"].map(String::from).into();
+        assert_eq!(demote(order, &cooling), ["d", "b", "c", "a"]);
+    }
+
+    #[test]
+    fn cooldowns_parse_back_from_their_display() {
+        for c in [Cooldown::Students(3), Cooldown::Minutes(2.5)] {
+            assert_eq!(c.to_string().parse::<Cooldown>().unwrap(), c);
+        }
+        assert_eq!(
+            "10min".parse::<Cooldown>().unwrap(),
+            Cooldown::Minutes(10.0)
+        );
+        assert!("-1m".parse::<Cooldown>().is_err());
+        assert!("3h".parse::<Cooldown>().is_err());
+    }
+}
@@ -0,0 +1,176 @@
+// The append-only log of every interaction with a student. The roster
+// only holds totals; this holds what happened, when, and in which
+// session, so the totals can be audited and rebuilt. What can happen
+// is up to each course: its outcomes say which key records them, which
+// counter they add to and how much, and whether that sways who is
+// picked.
+
+use std::{fmt, str::FromStr};
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::{persist, StudentKey};
+
+/// Which of a student's counters an outcome adds to.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Counter {
+    #[default]
+    ParticipationScore,
+    Deferrals,
+    Absent,
+    /// Just logged
+    None,
+}
+
+impl fmt::Display for Counter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            Counter::ParticipationScore => "participation_score",
+            Counter::Deferrals => "deferrals",
+            Counter::Absent => "absent",
+            Counter::None => "none",
+        };
+        write!(f, "{s}")
+    }
+}
+
+impl FromStr for Counter {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "participation_score" => Ok(Counter::ParticipationScore),
+            "deferrals" => Ok(Counter::Deferrals),
+            "absent" => Ok(Counter::Absent),
+            "none" => Ok(Counter::None),
+            _ => anyhow::bail!("Unknown counter \"{s}\"."),
+        }
+    }
+}
+
+/// A kind of outcome a student can be recorded with, as a course's
+/// rubric defines it.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Outcome {
+    /// What it is logged and shown as, e.g. `good answer`
+    pub name: String,
+    /// The key that records it
+    pub key: char,
+    /// The counter it adds to [default: participation_score]
+    #[serde(default)]
+    pub counter: Counter,
+    /// How much it adds [default: 1]
+    #[serde(default = "one")]
+    pub points: usize,
+    /// Whether it moves the score students are selected by, when its
+    /// counter is the participation score or deferrals [default: true]
+    #[serde(default = "yes")]
+    pub weighted: bool,
+}
+
+fn one() -> usize {
+    1
+}
+
+fn yes() -> bool {
+    true
+}
+
+impl Outcome {
+    fn new(name: &str, key: char, counter: Counter, points: usize) -> Self {
+        Outcome {
+            name: name.to_string(),
+            key,
+            counter,
+            points,
+            weighted: true,
+        }
+    }
+
+    /// A new event of this outcome for the student with `email`.
+    pub fn event(&self, session: &str, email: &StudentKey) -> Event {
+        Event {
+            timestamp: Local::now(),
+            session: session.to_string(),
+            email: email.clone(),
+            outcome: self.name.clone(),
+            undo: false,
+            counter: self.counter,
+            points: self.points,
+            weighted: self.weighted,
+        }
+    }
+}
+
+/// The outcomes a course has unless it defines its own: answers, plain
+/// or graded, questions asked, deferrals, and absences.
+pub fn default_outcomes() -> Vec<Outcome> {
+    vec![
+        Outcome::new("answer", 'a', Counter::ParticipationScore, 1),
+        Outcome::new("partial answer", '1', Counter::ParticipationScore, 1),
+        Outcome::new("good answer", '2', Counter::ParticipationScore, 2),
+        Outcome::new("excellent answer", '3', Counter::ParticipationScore, 3),
+        Outcome::new("question", '?', Counter::ParticipationScore, 1),
+        Outcome::new("absent", 'n', Counter::Absent, 1),
+        Outcome::new("defer", 'd', Counter::Deferrals, 1),
+    ]
+}
+
+/// What happened to a student. It keeps what its outcome did, so that
+/// changing the outcomes later doesn't rewrite the past.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Event {
+    pub timestamp: DateTime<Local>,
+    pub session: String,
+    pub email: StudentKey,
+    /// The name of its outcome
+    pub outcome: String,
+    /// This event reverses an earlier one with the same outcome
+    pub undo: bool,
+    /// What it added to
+    pub counter: Counter,
+    /// How much it added
+    pub points: usize,
+    /// Whether it moved the score students are selected by
+    pub weighted: bool,
+}
+
+impl Event {
+    /// How much it moved a student's participation score less
+    /// deferrals, or back again for an undo.
+    pub fn counted(&self) -> isize {
+        let n = match self.counter {
+            Counter::ParticipationScore => self.points as isize,
+            Counter::Deferrals => -(self.points as isize),
+            Counter::Absent | Counter::None => 0,
+        };
+        if self.undo {
+            -n
+        } else {
+            n
+        }
+    }
+
+    /// How much it moved the score students are selected by.
+    pub fn weight(&self) -> isize {
+        if self.weighted {
+            self.counted()
+        } else {
+            0
+        }
+    }
+}
+
+/// The tab-delimited log file of events, next to the roster.
+pub type EventLog = persist::Log<Event>;
+
+/// A session is identified by the time the program was started, down
+/// to the microsecond, so that scripts running one command after
+/// another each get a session of their own. The ids still sort in the
+/// order the sessions started.
+pub fn new_session() -> String {
+    Local::now().format("%Y-%m-%dT%H:%M:%S%.6f").to_string()
+}
@@ -0,0 +1,132 @@
+// Participation grades, worked out from the roster's counters by a
+// formula each course configures, and written out in a form the LMS
+// gradebooks take as an upload. Canvas matches rows on `SIS Login ID`
+// and Moodle on `Email address`, which we fill in with the email; the
+// plain format is just email, name, and grade.
+
+use std::{collections::HashMap, io};
+
+use serde::Deserialize;
+
+use crate::{Student, StudentKey};
+
+/// How counters become a grade: points for the participation score,
+/// which answers and questions earn, up to a number of them, less
+/// penalties for deferrals and absences, never below zero,
+/// and then capped at, or curved to, the points a full grade is worth.
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Formula {
+    /// Points for each point of participation score [default: 1]
+    pub per_answer: Option<f64>,
+    /// Participation score beyond this earns nothing more
+    pub max_answers: Option<usize>,
+    /// Points off for each deferral [default: 0]
+    pub per_deferral: Option<f64>,
+    /// Points off for each absence [default: 0]
+    pub per_absence: Option<f64>,
+    /// What a full grade is worth; nobody gets more
+    pub points: Option<f64>,
+    /// Scale everyone's grade so the best in the class gets full points
+    pub curve: Option<bool>,
+}
+
+impl Formula {
+    /// `self`, with whatever `over` sets taking precedence.
+    pub fn merge(self, over: Formula) -> Formula {
+        Formula {
+            per_answer: over.per_answer.or(self.per_answer),
+            max_answers: over.max_answers.or(self.max_answers),
+            per_deferral: over.per_deferral.or(self.per_deferral),
+            per_absence: over.per_absence.or(self.per_absence),
+            points: over.points.or(self.points),
+            curve: over.curve.or(self.curve),
+        }
+    }
+
+    /// A student's grade before any curve or cap.
+    fn raw(&self, s: &Student) -> f64 {
+        let answers = self
+            .max_answers
+            .map_or(s.participation_score, |m| s.participation_score.min(m));
+        let grade = self.per_answer.unwrap_or(1.0) * answers as f64
+            - self.per_deferral.unwrap_or(0.0) * s.deferrals as f64
+            - self.per_absence.unwrap_or(0.0) * s.absent as f64;
+        grade.max(0.0)
+    }
+
+    /// Everyone's grade, but for students who have dropped. A curve is
+    /// relative to the class, so it takes all of them at once.
+    pub fn grades(
+        &self,
+        students: &HashMap<StudentKey, Student>,
+    ) -> anyhow::Result<HashMap<StudentKey, f64>> {
+        let mut grades: HashMap<StudentKey, f64> = students
+            .iter()
+            .filter(|(_, s)| !s.inactive)
+            .map(|(k, s)| (k.clone(), self.raw(s)))
+            .collect();
+        if self.curve.unwrap_or(false) {
+            let Some(points) = self.points else {
+                anyhow::bail!("A curve needs the points a full grade is worth.");
+            };
+            let best = grades.values().copied().fold(0.0, f64::max);
+            if best > 0.0 {
+                for g in grades.values_mut() {
+                    *g *= points / best;
+                }
+            }
+        }
+        if let Some(points) = self.points {
+            for g in grades.values_mut() {
+                *g = g.min(points);
+            }
+        }
+        Ok(grades)
+    }
+}
+
+pub const FORMATS: [&str; 3] = ["canvas", "moodle", "plain"];
+
+/// Write everyone's grade to `w` as a gradebook upload in `format`, with
+/// the grades under `column`, sorted by name. Students who have dropped
+/// are left out.
+pub fn write(
+    w: impl io::Write,
+    students: &HashMap<StudentKey, Student>,
+    formula: &Formula,
+    format: &str,
+    column: &str,
+) -> anyhow::Result<()> {
+    let headers: &[&str] = match format {
+        "canvas" => &["Student", "ID", "SIS User ID", "SIS Login ID", "Section"],
+        "moodle" => &["Email address"],
+        "plain" => &["email", "name"],
+        _ => anyhow::bail!(
+            "Unknown format \"{format}\", expected one of {}.",
+            FORMATS.join(", ")
+        ),
+    };
+    let grades = formula.grades(students)?;
+    let mut students: Vec<&Student> = students.values().filter(|s| !s.inactive).collect();
+    students.sort_by(|a, b| a.name.cmp(&b.name).then(a.email.cmp(&b.email)));
+
+    let mut w = csv::Writer::from_writer(w);
+    w.write_record(headers.iter().chain([&column]))?;
+    for s in students {
+        let grade = format_grade(grades[&s.email]);
+        match format {
+            "canvas" => w.write_record([&s.name, "", "", &s.email, "", &grade])?,
+            "moodle" => w.write_record([&s.email, &grade])?,
+            _ => w.write_record([&s.email, &s.name, &grade])?,
+        }
+    }
+    w.flush()?;
+    Ok(())
+}
+
+// Up to two decimal places, without trailing zeros: 9, 8.5, 7.33.
+fn format_grade(g: f64) -> String {
+    let s = format!("{g:.2}");
+    s.trim_end_matches('0').trim_end_matches('.').to_string()
+}
@@ -0,0 +1,191 @@
+// Rosters from the gradebook exports of learning management systems.
+// Each names its columns its own way: Canvas has `Student` (as "Last,
+// First") and `SIS Login ID`, Moodle has `First name`, `Surname`, and
+// `Email address`, and Blackboard has `First Name`, `Last Name`, and
+// `Username` or `Email`. Columns are recognized by those names unless a
+// `Mapping` says which to use. Imported students are merged into the
+// roster: newcomers start at zero, and everyone already on it keeps
+// their counters.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::Path,
+};
+
+use serde::Deserialize;
+
+use crate::{Student, StudentKey};
+
+/// Which of an export's columns hold what. Anything left unset is
+/// looked for by the usual names.
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Mapping {
+    /// The column with students' full names
+    pub name: Option<String>,
+    /// The columns with their first and last names, instead of `name`
+    pub first_name: Option<String>,
+    pub last_name: Option<String>,
+    /// The column with their emails, or usernames to go with `domain`
+    pub email: Option<String>,
+    /// Added to usernames to make emails, e.g. `school.edu`
+    pub domain: Option<String>,
+}
+
+impl Mapping {
+    /// `self`, with whatever `over` sets taking precedence.
+    pub fn merge(self, over: Mapping) -> Mapping {
+        Mapping {
+            name: over.name.or(self.name),
+            first_name: over.first_name.or(self.first_name),
+            last_name: over.last_name.or(self.last_name),
+            email: over.email.or(self.email),
+            domain: over.domain.or(self.domain),
+        }
+    }
+}
+
+// What the columns are called in the exports we know of, most telling
+// first, compared without regard to case.
+const NAMES: [&str; 4] = ["name", "student", "student name", "full name"];
+const FIRST_NAMES: [&str; 2] = ["first name", "firstname"];
+const LAST_NAMES: [&str; 3] = ["last name", "lastname", "surname"];
+const EMAILS: [&str; 6] = [
+    "email",
+    "email address",
+    "e-mail",
+    "sis login id",
+    "login id",
+    "username",
+];
+
+/// What importing changed.
+#[derive(Debug, Default)]
+pub struct Summary {
+    /// The names of the students new to the roster
+    pub added: Vec<String>,
+    /// How many students' names changed
+    pub renamed: usize,
+    /// How many students were on the roster already, as they were
+    pub unchanged: usize,
+    /// The students still on the roster who aren't in the export
+    pub missing: Vec<String>,
+}
+
+/// The students in the export at `path`, with nothing on record. The
+/// export may be comma or tab delimited; rows without an email, such as
+/// Canvas's "Points Possible", are skipped.
+pub fn read(path: &Path, mapping: &Mapping) -> anyhow::Result<Vec<Student>> {
+    let text = fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("Could not read {}: {e}", path.display()))?;
+    let first = text.lines().next().unwrap_or_default();
+    let delimiter = if first.contains('\t') && !first.contains(',') {
+        b'\t'
+    } else {
+        b','
+    };
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .flexible(true)
+        .from_reader(text.as_bytes());
+
+    let headers = reader.headers()?.clone();
+    let column = |given: &Option<String>, known: &[&str]| -> anyhow::Result<Option<usize>> {
+        match given {
+            Some(c) => match headers.iter().position(|h| h.trim() == c) {
+                Some(i) => Ok(Some(i)),
+                None => anyhow::bail!(
+                    "{} has no column \"{c}\"; its columns are {}.",
+                    path.display(),
+                    headers.iter().collect::<Vec<_>>().join(", ")
+                ),
+            },
+            None => Ok(known
+                .iter()
+                .find_map(|k| headers.iter().position(|h| h.trim().to_lowercase() == *k))),
+        }
+    };
+
+    let email = column(&mapping.email, &EMAILS)?.ok_or_else(|| {
+        anyhow::anyhow!(
+            "Could not tell which column of {} has emails; name it with --email.",
+            path.display()
+        )
+    })?;
+    // A full name is used unless first and last names are asked for.
+    let split = mapping.first_name.is_some() || mapping.last_name.is_some();
+    let name = if split {
+        None
+    } else {
+        column(&mapping.name, &NAMES)?
+    };
+    let (first_name, last_name) = match name {
+        Some(_) => (None, None),
+        None => (
+            column(&mapping.first_name, &FIRST_NAMES)?,
+            column(&mapping.last_name, &LAST_NAMES)?,
+        ),
+    };
+    if name.is_none() && (first_name.is_none() || last_name.is_none()) {
+        anyhow::bail!(
+            "Could not tell which columns of {} have names; name them with --name, \
+             or --first-name and --last-name.",
+            path.display()
+        );
+    }
+
+    let mut students = Vec::new();
+    for record in reader.records() {
+        let record = record?;
+        let field = |i: Option<usize>| i.and_then(|i| record.get(i)).unwrap_or("").trim();
+
+        let mut key = field(Some(email)).to_string();
+        if key.is_empty() {
+            continue;
+        }
+        if let (false, Some(domain)) = (key.contains('@'), &mapping.domain) {
+            key = format!("{key}@{}", domain.trim_start_matches('@'));
+        }
+        let name = match name {
+            // Canvas lists students as "Last, First".
+            Some(i) => match field(Some(i)).split_once(", ") {
+                Some((last, first)) => format!("{first} {last}"),
+                None => field(Some(i)).to_string(),
+            },
+            None => format!("{} {}", field(first_name), field(last_name)),
+        };
+        students.push(Student::new(name.trim().to_string(), key));
+    }
+    Ok(students)
+}
+
+/// Fold `imported` into `roster`. Newcomers are added, and names are
+/// brought up to date, but nobody's counters change and nobody is
+/// removed.
+pub fn merge(roster: &mut HashMap<StudentKey, Student>, imported: Vec<Student>) -> Summary {
+    let mut summary = Summary::default();
+    let mut seen = HashSet::new();
+    for s in imported {
+        seen.insert(s.email.clone());
+        match roster.get_mut(&s.email) {
+            Some(existing) if existing.name != s.name => {
+                existing.name = s.name;
+                summary.renamed += 1;
+            }
+            Some(_) => summary.unchanged += 1,
+            None => {
+                summary.added.push(s.name.clone());
+                roster.insert(s.email.clone(), s);
+            }
+        }
+    }
+    summary.missing = roster
+        .values()
+        .filter(|s| !s.inactive && !seen.contains(&s.email))
+        .map(|s| s.name.clone())
+        .collect();
+    summary.added.sort();
+    summary.missing.sort();
+    summary
+}
@@ -0,0 +1,26 @@
+// The core of participation tracking, without a terminal: the roster
+// model, where it is stored, how students are picked, and the session
+// `App` that ties them together. The `participation` binary is a
+// ratatui frontend on top of this, in `tui` behind the feature of the
+// same name.
+
+pub mod app;
+pub mod attendance;
+pub mod calling;
+pub mod cli;
+pub mod config;
+pub mod cooldown;
+pub mod events;
+pub mod grade;
+pub mod import;
+pub mod notes;
+pub mod persist;
+pub mod replay;
+pub mod selection;
+pub mod store;
+mod student;
+pub mod sync;
+#[cfg(feature = "tui")]
+pub mod tui;
+
+pub use student::{Student, StudentKey, COLORS, NUM_COLORS};
@@ -1,482 +1,309 @@
-// Mostly an example taken from https://github.com/ratatui-org/ratatui/blob/main/examples/user_input.rs
+// The participation binary: the command line, and the terminal set up
+// for the `tui` frontend.
 
-use std::{cmp, env, ffi::OsString, fmt, fs::File, io};
-
-use fuzzy_matcher::skim::SkimMatcherV2;
-use fuzzy_matcher::FuzzyMatcher;
-
-use anyhow;
-use serde::{Deserialize, Serialize};
-
-use std::collections::HashMap;
-
-use rand::prelude::*;
+use std::{
+    io::{self, BufRead, Write},
+    path::{Path, PathBuf},
+};
 
+use clap::{error::ErrorKind, Args, CommandFactory, Parser, Subcommand};
 use ratatui::{
-    backend::{Backend, CrosstermBackend},
+    backend::CrosstermBackend,
     crossterm::{
-        event::{
-            self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind,
-            KeyModifiers,
-        },
+        event::{DisableMouseCapture, EnableMouseCapture},
         execute,
         terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
     },
-    layout::{Constraint, Layout, Position},
-    prelude::{Alignment, Rect},
-    style::{Color, Modifier, Style, Stylize},
-    text::{Line, Span, Text},
-    widgets::{Block, Clear, List, ListItem, Padding, Paragraph},
-    Frame, Terminal,
+    Terminal,
 };
 
-enum InputMode {
-    Command,
-    Searching,
-    Student,
-}
-
-#[derive(Clone)]
-enum DisplayMode {
-    Command,
-    Searching,
-}
+use participation::{
+    app::{App, Section},
+    calling::Calling,
+    cli,
+    config::Config,
+    grade::Formula,
+    import::{Mapping, Summary},
+    replay, store, tui,
+};
 
-type StudentKey = String;
-#[derive(Debug, Clone, Deserialize, Serialize)]
-struct Student {
-    name: String,
-    email: StudentKey,
-    participation_score: usize,
-    deferrals: usize,
-    absent: usize,
-    #[serde(skip_serializing, default)]
-    answered_today: usize,
-    #[serde(skip_serializing, default)]
-    color: usize, // the offset into COLORS
+/// Track who has participated in class, and pick who to call on next.
+///
+/// Without a command, opens the roster in the terminal UI, or each of
+/// several rosters in a tab of its own, for teaching more than one
+/// section. Settings are
+/// read from the course's participation.toml next to the roster (or in
+/// the current directory), and from
+/// $XDG_CONFIG_HOME/participation/config.toml, with the options here
+/// taking precedence.
+#[derive(Parser)]
+#[command(version, subcommand_precedence_over_arg = true)]
+struct Cli {
+    #[command(flatten)]
+    options: Options,
+    /// The rosters to open, if not given by --roster or a config file
+    #[arg(value_name = "ROSTER")]
+    files: Vec<PathBuf>,
+    #[command(subcommand)]
+    command: Option<Command>,
 }
 
-const COLORS: &str = "🔴🟠🟡🟢🔵";
-const NUM_COLORS: usize = 5;
-
-impl fmt::Display for Student {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let flames = "🔥".to_string().repeat(self.answered_today);
-        let cs: Vec<_> = COLORS.chars().collect();
-        write!(
-            f,
-            "{}{:3} {} {} {}",
-            cs[self.color], self.participation_score, flames, self.name, flames
-        )
+impl Cli {
+    /// A roster given both ways would leave one of them unused. Clap
+    /// can't tell, since --roster can come after the command.
+    fn validate(self) -> Result<Self, clap::Error> {
+        if !self.files.is_empty() && self.options.roster.is_some() {
+            return Err(Cli::command().error(
+                ErrorKind::ArgumentConflict,
+                "a roster can be given as ROSTER or with --roster, not both",
+            ));
+        }
+        Ok(self)
     }
 }
 
-/// App holds the state of the application
-struct App {
-    /// Backing file containing all of the students
-    db: OsString,
-    /// Current value of the input box
-    input: String,
-    /// Position of cursor in the editor area.
-    character_index: usize,
-    /// How the main screen should render
-    display_mode: DisplayMode,
-    /// Display the selected student in a popout
-    student_display: Option<Student>,
-    /// All students, indexed by github id
-    students: HashMap<StudentKey, Student>,
-    /// The order to display students outside of search mode
-    order: Vec<StudentKey>,
-    /// The filtered, sorted view of students
-    view: Vec<StudentKey>,
-    /// The offset of the selected entry into the view
-    selection: Option<usize>,
+#[derive(Args)]
+struct Options {
+    /// The roster: a delimited file, or an SQLite database (.db, .sqlite, .sqlite3)
+    #[arg(long, short, global = true)]
+    roster: Option<PathBuf>,
+    /// Where to write the session's output [default: <ROSTER>.out]
+    #[arg(long, short, global = true)]
+    output: Option<PathBuf>,
+    /// What separates the roster's fields: a character, or "tab" [default: tab]
+    #[arg(long, short, global = true)]
+    delimiter: Option<String>,
+    /// The kind of store, csv or sqlite [default: by extension]
+    #[arg(long, global = true)]
+    store: Option<String>,
+    /// How students are picked: biased, uniform, round-robin,
+    /// least-recent, lowest, or decayed[:14d|:3sessions] [default: biased]
+    #[arg(long, global = true)]
+    strategy: Option<String>,
+    /// Seed the draws, to repeat a session's orders
+    #[arg(long, global = true)]
+    seed: Option<u64>,
+    /// Keep whoever was called at the bottom of the list until N others
+    /// have been, or N minutes have passed (Nm)
+    #[arg(long, global = true)]
+    cooldown: Option<String>,
+    /// The UI's colors: dark, light, or mono [default: dark]
+    #[arg(long, global = true)]
+    theme: Option<String>,
+    /// Read the course's settings from this file instead of participation.toml
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
 }
 
-fn deserialize_file(file_path: &OsString) -> anyhow::Result<HashMap<StudentKey, Student>> {
-    let file = File::open(file_path)?;
-
-    let mut f = csv::ReaderBuilder::new()
-        .delimiter(b'\t')
-        .comment(Some(b'#'))
-        .flexible(true)
-        .from_reader(file);
-
-    let mut students = HashMap::new();
-    for s_rec in f.deserialize() {
-        let s: Student = s_rec?;
-        let email = s.email.trim().to_string();
-
-        students.insert(
-            email.clone(),
-            Student {
-                name: s.name.trim().to_string(),
-                email,
-                participation_score: s.participation_score,
-                deferrals: s.deferrals,
-                absent: s.absent,
-                answered_today: 0,
-                color: 0,
-            },
-        );
-    }
-
-    Ok(students)
+/// Which columns of an LMS export hold what, where they aren't named as
+/// usual.
+#[derive(Args)]
+struct Columns {
+    /// The column with students' full names
+    #[arg(long)]
+    name: Option<String>,
+    /// The column with their first names, with --last-name
+    #[arg(long)]
+    first_name: Option<String>,
+    /// The column with their last names, with --first-name
+    #[arg(long)]
+    last_name: Option<String>,
+    /// The column with their emails or usernames
+    #[arg(long)]
+    email: Option<String>,
+    /// Turn usernames into emails at this domain
+    #[arg(long)]
+    domain: Option<String>,
 }
 
-impl App {
-    fn new(db: OsString) -> anyhow::Result<Self> {
-        let students = deserialize_file(&db)?;
-
-        let mut s = Self {
-            db,
-            input: String::new(),
-            display_mode: DisplayMode::Command,
-            student_display: None,
-            students,
-            character_index: 0,
-            selection: None,
-            view: Vec::new(),
-            order: Vec::new(),
-        };
-        s.randomize();
-        Ok(s)
-    }
-
-    fn move_cursor_left(&mut self) {
-        let cursor_moved_left = self.character_index.saturating_sub(1);
-        self.character_index = self.clamp_cursor(cursor_moved_left);
-    }
-
-    fn move_cursor_right(&mut self) {
-        let cursor_moved_right = self.character_index.saturating_add(1);
-        self.character_index = self.clamp_cursor(cursor_moved_right);
-    }
-
-    fn move_selection_up(&mut self) {
-        if self.selection == None {
-            return;
-        }
-        let sel = self.selection.unwrap();
-        if sel > 0 {
-            self.selection = Some(sel - 1);
-        }
-    }
-
-    fn move_selection_down(&mut self) {
-        if self.selection == None {
-            return;
-        }
-        let sel = self.selection.unwrap();
-        if sel < self.view.len() - 1 {
-            self.selection = Some(sel + 1);
-        }
-    }
-
-    fn selection_reset(&mut self) {
-        if self.view.len() > 0 {
-            self.selection = Some(0);
-        } else {
-            self.selection = None;
+impl From<Columns> for Mapping {
+    fn from(c: Columns) -> Self {
+        Mapping {
+            name: c.name,
+            first_name: c.first_name,
+            last_name: c.last_name,
+            email: c.email,
+            domain: c.domain,
         }
     }
+}
 
-    fn students_view(&self) -> &Vec<StudentKey> {
-        &self.view
-    }
+#[derive(Subcommand)]
+enum Command {
+    /// Fold the session into the roster, archiving the old one
+    #[command(alias = "rotate")]
+    Commit,
+    /// List the sessions that drew orders, or show every order a session drew
+    Replay { session: Option<String> },
+    /// Copy a roster, with its history, uncommitted events, attendance, and notes, between stores
+    Convert { from: PathBuf, to: PathBuf },
+    /// Print the first N students of a fresh draw
+    Pick {
+        #[arg(default_value_t = 1)]
+        n: usize,
+    },
+    /// Log an outcome for a student, by its name in the course's
+    /// outcomes: by default answer, partial answer, good answer,
+    /// excellent answer, question, absent, or defer
+    Record { email: String, outcome: String },
+    /// Print a student's counters
+    Show { email: String },
+    /// Set how a student is called on: normal, exempt (never
+    /// cold-called), volunteer (only when they volunteer), or warn (a
+    /// heads up first)
+    Calling { email: String, calling: String },
+    /// Add the students in a gradebook export from Canvas, Blackboard,
+    /// or Moodle to the roster, creating it if need be
+    Import {
+        export: PathBuf,
+        #[command(flatten)]
+        columns: Columns,
+    },
+    /// Bring the roster in line with an enrollment list, in any format
+    /// import takes: add newcomers, rename, and mark whoever has
+    /// dropped as inactive
+    Sync {
+        enrollment: PathBuf,
+        #[command(flatten)]
+        columns: Columns,
+        /// Make every change without asking
+        #[arg(long, short)]
+        yes: bool,
+    },
+    /// Print everyone's participation grade, as set up in the config's
+    /// [grade] table, for uploading to a gradebook
+    Export {
+        /// canvas, moodle, or plain (email, name, grade)
+        #[arg(long, default_value = "plain")]
+        format: String,
+        /// What to head the grades with, e.g. the gradebook's column
+        #[arg(long, default_value = "Participation")]
+        column: String,
+    },
+    /// Print the current roster
+    List {
+        /// name, email, or score (lowest first)
+        #[arg(long, default_value = "name")]
+        sort: String,
+    },
+}
 
-    fn selected_student(&self) -> Option<&Student> {
-        let sel = self.selection?;
-        assert!(self.view.len() >= sel);
-        Some(
-            &self
-                .students
-                .get(&self.view[sel])
-                .expect("View has a stale student not in the student db."),
-        )
-    }
+fn main() -> anyhow::Result<()> {
+    let cli = Cli::parse().validate().unwrap_or_else(|e| e.exit());
+    let o = cli.options;
+    let (roster, sections) = match &cli.files[..] {
+        [] => (o.roster, None),
+        [file] => (Some(file.clone()), None),
+        _ => (None, Some(cli.files)),
+    };
+    let given = Config {
+        roster,
+        sections,
+        output: o.output,
+        delimiter: o.delimiter,
+        store: o.store,
+        strategy: o.strategy,
+        seed: o.seed,
+        cooldown: o.cooldown,
+        theme: o.theme,
+        import: Mapping::default(),
+        grade: Formula::default(),
+        outcomes: None,
+    };
 
-    fn update_student_view(&mut self) {
-        let view = if self.input.len() != 0 {
-            // If there's an active search term, use fuzzy matching
-            let matcher = SkimMatcherV2::default();
-            let mut matched: Vec<(Student, i64)> = self
-                .students
-                .iter()
-                .filter_map(|(_, entry)| {
-                    if let Some(score) =
-                        matcher.fuzzy_match(&entry.name.to_lowercase(), &self.input.to_lowercase())
-                    {
-                        Some((entry.clone(), score))
-                    } else {
-                        None
-                    }
-                })
-                .collect();
-            matched.sort_by(|(_, a), (_, b)| b.cmp(&a));
-            matched.into_iter().map(|(s, _)| s.email).collect()
-        } else {
-            // Otherwise just the order is random, biased by
-            // participation score, see `randomize` below.
-            self.order
-                .iter()
-                .map(|key| {
-                    self.students
-                        .get(key)
-                        .expect("The order is inconsistent and has a student not in the db.")
-                        .email
-                        .clone()
-                })
-                .collect()
+    // The roster being converted is the one whose settings apply.
+    if let Some(Command::Convert { from, to }) = &cli.command {
+        let given = Config {
+            roster: Some(from.clone()),
+            sections: None,
+            ..given
         };
-
-        self.view = view;
-        self.selection_reset();
-    }
-
-    fn enter_char(&mut self, new_char: char) {
-        let index = self.byte_index();
-        self.input.insert(index, new_char);
-        self.move_cursor_right();
-        self.update_student_view();
+        return convert(&Config::discover(given, o.config.as_deref())?, to);
     }
 
-    /// Returns the byte index based on the character position.
-    ///
-    /// Since each character in a string can be contain multiple bytes, it's necessary to calculate
-    /// the byte index based on the index of the character.
-    fn byte_index(&self) -> usize {
-        self.input
-            .char_indices()
-            .map(|(i, _)| i)
-            .nth(self.character_index)
-            .unwrap_or(self.input.len())
-    }
+    let config = Config::discover(given, o.config.as_deref())?;
+    let options = config.store_options()?;
+    let Some(command) = cli.command else {
+        return run(&config, &options);
+    };
 
-    fn delete_char(&mut self) {
-        let is_not_cursor_leftmost = self.character_index != 0;
-        if is_not_cursor_leftmost {
-            // Method "remove" is not used on the saved text for deleting the selected char.
-            // Reason: Using remove on String works on bytes instead of the chars.
-            // Using remove would require special care because of char boundaries.
-
-            let current_index = self.character_index;
-            let from_left_to_current_index = current_index - 1;
-
-            // Getting all characters before the selected character.
-            let before_char_to_delete = self.input.chars().take(from_left_to_current_index);
-            // Getting all characters after selected character.
-            let after_char_to_delete = self.input.chars().skip(current_index);
-
-            // Put all characters together except the selected one.
-            // By leaving the selected one out, it is forgotten and therefore deleted.
-            self.input = before_char_to_delete.chain(after_char_to_delete).collect();
-            self.move_cursor_left();
+    let roster = config.roster()?;
+    let fresh = !roster.exists();
+    let mut store = store::open_with(roster.as_os_str(), &options)?;
+    match command {
+        Command::Commit => store.commit(),
+        Command::Replay { session: None } => print_sessions(store.as_ref()),
+        Command::Replay {
+            session: Some(session),
+        } => print_replay(store.as_ref(), &session),
+        Command::Convert { .. } => unreachable!("converting is done above"),
+        Command::Pick { n } => {
+            for s in cli::pick(store, &config.section_options()?, n)? {
+                println!("{} <{}>", s.name, s.email);
+            }
+            Ok(())
         }
-        self.update_student_view();
-    }
-
-    fn clamp_cursor(&self, new_cursor_pos: usize) -> usize {
-        new_cursor_pos.clamp(0, self.input.chars().count())
-    }
-
-    fn reset_cursor(&mut self) {
-        self.character_index = 0;
-    }
-
-    fn display_selected_student(&mut self) {
-        self.student_display = self.selected_student().map(|s| s.clone());
-    }
-
-    fn input_clear(&mut self) {
-        self.input.clear();
-        self.reset_cursor();
-        self.update_student_view();
-    }
-
-    fn input_mode(&self) -> InputMode {
-        if self.student_display.is_some() {
-            return InputMode::Student;
+        Command::Record { email, outcome } => {
+            let s = cli::record(store.as_mut(), &email, &outcome, &config.outcomes()?)?;
+            println!("Recorded {outcome} for {}", s.name);
+            Ok(())
         }
-        match self.display_mode {
-            DisplayMode::Command => InputMode::Command,
-            DisplayMode::Searching => InputMode::Searching,
+        Command::Show { email } => {
+            print_profile(&cli::show(store.as_ref(), &email)?);
+            Ok(())
         }
-    }
-
-    fn student_escape(&mut self) {
-        self.student_display = None;
-    }
-
-    fn student_absent(&mut self) {
-        assert!(self.student_display.is_some());
-        let s = self.student_display.as_ref().unwrap();
-
-        let s = self
-            .students
-            .get_mut(&s.email)
-            .expect("Student database became inconsistent with active student");
-
-        s.absent += 1;
-        self.update_data();
-        self.student_escape();
-    }
-
-    fn student_defer(&mut self) {
-        assert!(self.student_display.is_some());
-        let s = self.student_display.as_ref().unwrap();
-
-        let s = self
-            .students
-            .get_mut(&s.email)
-            .expect("Student database became inconsistent with active student");
-
-        s.deferrals += 1;
-        self.update_data();
-        self.student_escape();
-    }
-
-    fn student_answer(&mut self) {
-        assert!(self.student_display.is_some());
-        let s = self.student_display.as_ref().unwrap();
-
-        let s = self
-            .students
-            .get_mut(&s.email)
-            .expect("Student database became inconsistent with active student");
-        s.participation_score += 1;
-        s.answered_today += 1;
-        self.update_data();
-        self.student_escape();
-    }
-
-    // Brutally inefficient, but luckily my classes have only ~70
-    // students!
-    fn randomize(&mut self) {
-        let (max, min) =
-            self.students
-                .iter()
-                .fold((std::isize::MIN, std::isize::MAX), |(max, min), (_, s)| {
-                    (
-                        cmp::max(max, s.participation_score as isize - s.deferrals as isize),
-                        cmp::min(min, s.participation_score as isize - s.deferrals as isize),
-                    )
-                });
-
-        let mut bag = Vec::new();
-        let mut score_ordered = Vec::new();
-        let norm = (max - min) as usize;
-        let adjust = min.abs() as usize;
-        for (_, s) in self.students.iter_mut() {
-            // Normalize and scale the participation scores
-            assert!(adjust >= s.deferrals);
-            let p: usize = s.participation_score + adjust - s.deferrals;
-            let chances: usize = norm - p;
-            // And add a corresponding number of tokens -- more tokens
-            // for students with smaller `participation_score` to
-            // increase the chance their token is drawn.
-            for _ in 0..=chances {
-                bag.push(s.email.clone());
-            }
-
-            score_ordered.push((s.email.clone(), p));
+        Command::Calling { email, calling } => {
+            let s = cli::set_calling(store.as_mut(), &email, calling.parse()?)?;
+            println!("{} is called on as {}", s.name, s.calling);
+            Ok(())
         }
-        let mut rng = rand::thread_rng();
-        bag.shuffle(&mut rng);
-
-        let mut order = Vec::new();
-        for k in &bag {
-            if order.iter().find(|i| *i == k).is_none() {
-                order.push(k.clone());
+        Command::List { sort } => {
+            let mut w = csv::WriterBuilder::new()
+                .delimiter(b'\t')
+                .from_writer(io::stdout());
+            for s in cli::list(store.as_ref(), &sort)? {
+                w.serialize(s)?;
             }
+            w.flush()?;
+            Ok(())
         }
-        assert!(self.students.len() == order.len());
-
-        // sort by the computed score
-        score_ordered.sort_by(|(_, p0), (_, p2)| p0.cmp(p2));
-        // Calculate the colors by separating students into fifths
-        for (_, s) in self.students.iter_mut() {
-	    // Get the student's index, sorted by score.
-            let (i, p) = score_ordered.iter().enumerate().find_map(|(i, (e, p))| {
-                if s.email == *e {
-                    Some((i, p))
-                } else {
-                    None
-                }
-            }).expect("Could not find student when an entry must exist.");
-
-	    let quintile_sz: usize = (score_ordered.len() as f64 / NUM_COLORS as f64).ceil() as usize;
-	    let quintile: usize = i / quintile_sz;
-	    assert!(quintile < NUM_COLORS);
-	    let mut final_quintile = quintile;
-	    // this is odd, but necessary: if this student has the
-	    // same score as the last student in the previous
-	    // quintile, inherit the previous quitile. This must be
-	    // done recursively for all previous quintiles to handle
-	    // the case that all students that all students have the
-	    // same score.
-	    for q in (1..=quintile).rev() {
-		let prev_quint_score = score_ordered[(q * quintile_sz) - 1].1;
-
-		// The last element of the previous quintile has a
-		// different score, so we know we belong in our
-		    // quintile
-		if *p != prev_quint_score {
-		    break;
-		}
-		final_quintile = q - 1;
-	    }
-	    s.color = final_quintile;
-	    assert!(s.color < NUM_COLORS);
+        Command::Export { format, column } => cli::export(
+            io::stdout(),
+            store.as_ref(),
+            &config.grade,
+            &format,
+            &column,
+        ),
+        Command::Import { export, columns } => {
+            let mapping = config.import.clone().merge(columns.into());
+            let summary = cli::import(store.as_mut(), fresh, &export, &mapping)?;
+            print_import(&summary, &export);
+            Ok(())
         }
-
-        self.order = order;
-        self.update_student_view();
-        self.selection_reset();
-    }
-
-    // The data has been updated, so we need to update all
-    // corresponding data-structures, and the db.
-    fn update_data(&mut self) {
-        self.randomize();
-        self.serialize_csv().unwrap();
-        // TODO: write back to the DB.
-    }
-
-    fn serialize_csv(&self) -> anyhow::Result<()> {
-        let path = format!("{}.out", self.db.clone().into_string().unwrap());
-        let newfile = File::create(&path);
-
-        let file = if newfile.is_ok() {
-            newfile.unwrap()
-        } else {
-            File::open(&path)?
-        };
-        let mut writer = csv::WriterBuilder::new()
-            .has_headers(true)
-            .delimiter(b'\t')
-            .flexible(true)
-            .from_writer(file);
-
-        for (_, s) in &self.students {
-            writer.serialize(s)?;
+        Command::Sync {
+            enrollment,
+            columns,
+            yes,
+        } => {
+            let mapping = config.import.clone().merge(columns.into());
+            sync(store.as_mut(), &enrollment, &mapping, yes)
         }
-
-        return Ok(());
     }
 }
 
-fn main() -> anyhow::Result<()> {
-    //Result<(), Box<dyn Error>> {
-    if env::args_os().len() != 2 {
-        println!("Usage: {} student_list.csv\nwhere the csv file is tab-delimited and can have arbitrary names.", env::args_os().nth(0).unwrap().to_str().unwrap());
-        return Err(anyhow::anyhow!("Incorrect number of arguments"));
+/// Open every section in the terminal UI.
+fn run(config: &Config, options: &store::Options) -> anyhow::Result<()> {
+    let theme = tui::Theme::by_name(config.theme.as_deref().unwrap_or("dark"))?;
+    let section = config.section_options()?;
+    let mut sections = Vec::new();
+    for roster in config.rosters()? {
+        sections.push(Section::new(
+            section_name(roster),
+            store::open_with(roster.as_os_str(), options)?,
+            &section,
+        )?);
     }
-    let file_path = env::args_os()
-        .nth(1)
-        .ok_or(anyhow::anyhow!("Argument {} not provided.", 1))?;
-
-    let app = App::new(file_path)?;
+    let mut app = App::new(sections);
 
     // setup terminal
     enable_raw_mode()?;
@@ -486,7 +313,7 @@ fn main() -> anyhow::Result<()> {
     let mut terminal = Terminal::new(backend)?;
 
     // create app and run it
-    let res = run_app(&mut terminal, app);
+    let res = tui::run_app(&mut terminal, &mut app, &mut tui::Crossterm, &theme);
 
     // restore terminal
     disable_raw_mode()?;
@@ -504,244 +331,229 @@ fn main() -> anyhow::Result<()> {
     Ok(())
 }
 
-fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> io::Result<()> {
-    loop {
-        terminal.draw(|f| ui(f, &app))?;
-
-        if let Event::Key(key) = event::read()? {
-            match app.input_mode() {
-                InputMode::Command => match key.code {
-                    KeyCode::Char('s') | KeyCode::Char('/') => {
-                        app.display_mode = DisplayMode::Searching;
-                    }
-                    KeyCode::Char('r') => {
-                        app.randomize();
-                    }
-                    KeyCode::Char('q') => {
-                        return Ok(());
-                    }
-                    KeyCode::Down => {
-                        app.move_selection_down();
-                    }
-                    KeyCode::Up => {
-                        app.move_selection_up();
-                    }
-                    KeyCode::Char('p') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                        app.move_selection_up();
-                    }
-                    KeyCode::Char('n') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                        app.move_selection_down();
-                    }
-                    KeyCode::Enter => {
-                        app.display_selected_student();
-                    }
-                    _ => {}
-                },
-                InputMode::Searching if key.kind == KeyEventKind::Press => match key.code {
-                    KeyCode::Enter => {
-                        app.display_selected_student();
-                    }
-                    KeyCode::Char('p') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                        app.move_selection_up();
-                    }
-                    KeyCode::Char('n') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                        app.move_selection_down();
-                    }
-                    KeyCode::Backspace => {
-                        app.delete_char();
-                    }
-                    KeyCode::Left => {
-                        app.move_cursor_left();
-                    }
-                    KeyCode::Right => {
-                        app.move_cursor_right();
-                    }
-                    KeyCode::Down => {
-                        app.move_selection_down();
-                    }
-                    KeyCode::Up => {
-                        app.move_selection_up();
-                    }
-                    KeyCode::Char('g') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                        app.display_mode = DisplayMode::Command;
-                        app.input_clear();
-                    }
-                    KeyCode::Esc => {
-                        app.display_mode = DisplayMode::Command;
-                        app.input_clear();
-                    }
-                    KeyCode::Char(to_insert) => {
-                        app.enter_char(to_insert);
-                    }
-                    _ => {}
-                },
-                InputMode::Searching => {}
-                InputMode::Student => match key.code {
-                    // If student defers/delays
-                    KeyCode::Char('d') => {
-                        app.student_defer();
-                    }
-                    // If student is absent, or provides no answer
-                    KeyCode::Char('n') => {
-                        app.student_absent();
-                    }
-                    // If student answers like a boss
-                    KeyCode::Char('a') => {
-                        app.student_answer();
-                    }
-                    KeyCode::Char('g') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                        app.student_escape();
-                    }
-                    KeyCode::Esc => {
-                        app.student_escape();
-                    }
-                    _ => {}
-                },
+/// Print everything there is to know about a student.
+fn print_profile(p: &cli::Profile) {
+    let s = &p.student;
+    println!("{} <{}>", s.name, s.email);
+    println!("participation\t{}", s.participation_score);
+    println!("deferrals\t{}", s.deferrals);
+    println!("absent\t{}", s.absent);
+    if let Some(a) = p.today {
+        println!("today\t{a}");
+    }
+    if let Some(t) = p.last_called {
+        println!("last called\t{}", t.format("%Y-%m-%d %H:%M"));
+    }
+    if s.calling != Calling::Normal {
+        println!("calling\t{}", s.calling);
+    }
+    if let Some(r) = &p.notes {
+        if !r.tags.is_empty() {
+            println!("tags\t{}", r.tags);
+        }
+        if !r.notes.is_empty() {
+            println!("notes\t{}", r.notes);
+        }
+    }
+}
+
+/// Print what importing the export at `path` changed.
+fn print_import(summary: &Summary, path: &Path) {
+    for name in &summary.added {
+        println!("Added {name}");
+    }
+    println!(
+        "{} added, {} renamed, {} unchanged.",
+        summary.added.len(),
+        summary.renamed,
+        summary.unchanged
+    );
+    if !summary.missing.is_empty() {
+        println!(
+            "Not in {}, but left on the roster (sync drops them): {}",
+            path.display(),
+            summary.missing.join(", ")
+        );
+    }
+}
+
+/// Bring the roster in line with the enrollment list at `path`, asking
+/// about each change unless `yes`.
+fn sync(
+    store: &mut dyn store::Store,
+    path: &Path,
+    mapping: &Mapping,
+    yes: bool,
+) -> anyhow::Result<()> {
+    let changes = cli::enrollment_changes(store, path, mapping)?;
+    if changes.is_empty() {
+        println!("The roster is up to date.");
+        return Ok(());
+    }
+
+    let mut all = yes;
+    let mut agreed = Vec::new();
+    let mut answers = io::stdin().lock().lines();
+    for change in changes {
+        if !all {
+            print!("{change}? [y]es, [N]o, [a]ll, [q]uit: ");
+            io::stdout().flush()?;
+            // Running out of input is as good as quitting.
+            let Some(answer) = answers.next().transpose()? else {
+                break;
+            };
+            match answer.trim() {
+                "y" | "yes" => {}
+                "a" | "all" => all = true,
+                "q" | "quit" => break,
+                _ => continue,
             }
+        } else {
+            println!("{change}");
         }
+        agreed.push(change);
     }
+
+    let made = agreed.len();
+    cli::sync(store, agreed)?;
+    println!("Made {made} changes to the roster.");
+    Ok(())
 }
 
-fn ui(f: &mut Frame, app: &App) {
-    let vertical = Layout::vertical([
-        Constraint::Length(1),
-        Constraint::Length(3),
-        Constraint::Min(1),
-    ]);
-    let area = f.area();
-    let [help_area, input_area, students_area] = vertical.areas(area);
-
-    let (msg, style) = match app.input_mode() {
-        InputMode::Command => (
-            vec![
-                "q".bold(),
-                " = quit, ".into(),
-                "r".bold(),
-                " = 🎲 randomize (biased), ".into(),
-                "s".bold(),
-                " = search, ".into(),
-                "↑".bold(),
-                " and ".into(),
-                "↓".bold(),
-                " = navigate students.".into(),
-            ],
-            Style::default(),
-        ),
-        InputMode::Searching => (
-            vec![
-                "Esc".bold(),
-                " = go back, ".into(),
-                "Enter".bold(),
-                " = select a student, ".into(),
-                "↑".bold(),
-                " and ".into(),
-                "↓".bold(),
-                " = navigate students.".into(),
-            ],
-            Style::default(),
-        ),
-        InputMode::Student => (
-            vec![
-                "Esc".bold(),
-                " to go back, ".into(),
-                "a".bold(),
-                " = answer, ".into(),
-                "n".bold(),
-                " = absent or no answer ".into(),
-                "d".bold(),
-                " = defer.".into(),
-            ],
-            Style::default(),
-        ),
-    };
-    let text = Text::from(Line::from(msg)).patch_style(style);
-    let help_message = Paragraph::new(text);
-    f.render_widget(help_message, help_area);
-
-    let input = Paragraph::new(app.input.as_str())
-        .style(match app.display_mode {
-            DisplayMode::Searching => Style::default().fg(Color::Green),
-            _ => Style::default(),
-        })
-        .block(Block::bordered().title("Query"));
-    f.render_widget(input, input_area);
-    match app.display_mode {
-        // Hide the cursor. `Frame` does this by default, so we don't need to do anything here
-        DisplayMode::Command => {}
-
-        // Make the cursor visible and ask ratatui to put it at the specified coordinates after
-        // rendering
-        #[allow(clippy::cast_possible_truncation)]
-        DisplayMode::Searching => f.set_cursor_position(Position::new(
-            // Draw the cursor at the current position in the input field.
-            // This position is can be controlled via the left and right arrow key
-            input_area.x + app.character_index as u16 + 1,
-            // Move one line down, from the border to the input line
-            input_area.y + 1,
-        )),
+/// List the sessions that can be replayed, with how many draws each
+/// made.
+fn print_sessions(store: &dyn store::Store) -> anyhow::Result<()> {
+    let sessions = replay::sessions(store)?;
+    if sessions.is_empty() {
+        println!("No sessions to replay.");
+    }
+    for (session, draws) in sessions {
+        println!("{session}\t{draws} draws");
+    }
+    Ok(())
+}
+
+/// Copy `config`'s roster, opened as configured, to `to`, whose kind
+/// goes by its extension. Only the delimiter carries over, since the
+/// copy is a store of its own.
+fn convert(config: &Config, to: &Path) -> anyhow::Result<()> {
+    let options = config.store_options()?;
+    let from = store::open_with(config.roster()?.as_os_str(), &options)?;
+    let options = store::Options {
+        kind: None,
+        output: None,
+        ..options
     };
-    let students: Vec<ListItem> = app
-        .students_view()
-        .iter()
-        .enumerate()
-        .map(|(i, key)| {
-            let s = app
-                .students
-                .get(key)
-                .expect("View has inconsistent name with the student db.");
-            let content = if app.selection.is_some() && i == app.selection.unwrap() {
-                Line::from(Span::styled(
-                    format!("{s}"),
-                    Style::default().bg(Color::Green).fg(Color::Black),
-                ))
+    let mut to = store::open_with(to.as_os_str(), &options)?;
+    store::copy(from.as_ref(), to.as_mut())
+}
+
+/// Print every order `session` drew, with why anyone was moved down.
+fn print_replay(store: &dyn store::Store, session: &str) -> anyhow::Result<()> {
+    for r in replay::replay(store, session)? {
+        let d = &r.draw;
+        let cooldown = match d.cooldown.as_str() {
+            "" => String::new(),
+            c => format!(", cooldown {c}"),
+        };
+        println!(
+            "{} ({}{cooldown}, seed {}):",
+            d.timestamp.format("%Y-%m-%d %H:%M:%S"),
+            d.strategy,
+            d.seed
+        );
+        for (i, p) in r.order.iter().enumerate() {
+            let why = if p.cooling {
+                " (cooling down)"
+            } else if p.exempt {
+                " (not cold-called)"
+            } else if p.heads_up {
+                " (heads up)"
             } else {
-                Line::from(Span::raw(format!("{}", s)))
+                ""
             };
-            ListItem::new(content)
-        })
-        .collect();
-    let students = List::new(students).block(
-        Block::bordered()
-            .title("Students")
-            .padding(Padding::new(2, 2, 1, 1)),
-    );
-    f.render_widget(students, students_area);
-
-    if let Some(s) = &app.student_display {
-        let area = centered_rect(60, 20, area);
-        let block = Paragraph::new(format!("🎉{s}🎉"))
-            .style(Style::default())
-            .alignment(Alignment::Center)
-            .block(Block::bordered().title("Student ✋").padding(Padding::new(
-                0,
-                0,
-                area.height / 2 - 1,
-                0,
-            )))
-            .style(
-                Style::default()
-                    .bg(Color::Gray)
-                    .fg(Color::Black)
-                    .add_modifier(Modifier::BOLD),
-            );
-
-        f.render_widget(Clear, area); //this clears out the background
-        f.render_widget(block, area);
+            println!("{:4}. {} <{}>{why}", i + 1, p.name, p.email);
+        }
     }
+    Ok(())
 }
 
-fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
-    let popup_layout = Layout::vertical([
-        Constraint::Percentage((100 - percent_y) / 2),
-        Constraint::Percentage(percent_y),
-        Constraint::Percentage((100 - percent_y) / 2),
-    ])
-    .split(r);
-
-    Layout::horizontal([
-        Constraint::Percentage((100 - percent_x) / 2),
-        Constraint::Percentage(percent_x),
-        Constraint::Percentage((100 - percent_x) / 2),
-    ])
-    .split(popup_layout[1])[1]
+/// A section's tab is named for its roster, e.g. `cs101-02` for
+/// `rosters/cs101-02.csv`.
+fn section_name(roster: &Path) -> String {
+    roster
+        .file_stem()
+        .unwrap_or(roster.as_os_str())
+        .to_string_lossy()
+        .into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Cli {
+        Cli::try_parse_from(["participation"].iter().chain(args)).unwrap()
+    }
+
+    #[test]
+    fn a_roster_can_come_before_the_command() {
+        let cli = parse(&["r.csv", "list"]);
+        assert_eq!(cli.files, [PathBuf::from("r.csv")]);
+        assert!(matches!(cli.command, Some(Command::List { .. })));
+    }
+
+    #[test]
+    fn the_roster_option_goes_with_any_command() {
+        let cli = parse(&["--roster", "r.csv", "commit"]);
+        assert!(cli.files.is_empty());
+        assert_eq!(cli.options.roster, Some(PathBuf::from("r.csv")));
+        assert!(matches!(cli.command, Some(Command::Commit)));
+    }
+
+    #[test]
+    fn converting_reads_the_roster_as_configured() {
+        let dir = tempfile::tempdir().unwrap();
+        let from = dir.path().join("r.csv");
+        let to = dir.path().join("r.db");
+        std::fs::write(
+            &from,
+            "name,email,participation_score,deferrals,absent\n\
+             Ada Lovelace,ada@x.edu,2,0,0\n",
+        )
+        .unwrap();
+        let config = Config {
+            roster: Some(from),
+            delimiter: Some(",".to_string()),
+            ..Config::default()
+        };
+        convert(&config, &to).unwrap();
+
+        let students = store::open(to.as_os_str(), None).unwrap().load().unwrap();
+        assert_eq!(students["ada@x.edu"].participation_score, 2);
+    }
+
+    #[test]
+    fn a_roster_is_given_one_way_or_the_other() {
+        for args in [
+            &["--roster", "a.csv", "b.csv"][..],
+            &["a.csv", "--roster", "b.csv", "list"],
+            &["a.csv", "b.csv", "--roster", "c.csv"],
+            &["a.csv", "list", "--roster", "b.csv"],
+        ] {
+            let args = ["participation"].iter().chain(args);
+            let err = Cli::try_parse_from(args)
+                .and_then(Cli::validate)
+                .err()
+                .unwrap();
+            assert_eq!(err.kind(), clap::error::ErrorKind::ArgumentConflict);
+        }
+    }
+
+    #[test]
+    fn several_rosters_open_as_sections() {
+        let cli = parse(&["a.csv", "b.csv"]);
+        assert_eq!(cli.files, [PathBuf::from("a.csv"), PathBuf::from("b.csv")]);
+        assert!(cli.command.is_none());
+    }
 }
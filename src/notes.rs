@@ -0,0 +1,44 @@
+// An instructor's own notes and tags on students, e.g. "prefers written
+// questions", or a tag like `accommodation`. They aren't counters, so
+// like attendance they live beside the roster rather than in it, each
+// edit recorded as it is made, and commits leave them be.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::StudentKey;
+
+/// A student's notes and tags as of `timestamp`, replacing any earlier
+/// record for them.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Record {
+    pub timestamp: DateTime<Local>,
+    pub email: StudentKey,
+    pub notes: String,
+    /// Separated by spaces
+    pub tags: String,
+}
+
+/// Each student's latest record.
+pub fn latest(records: Vec<Record>) -> HashMap<StudentKey, Record> {
+    let mut latest = HashMap::new();
+    for r in records {
+        latest.insert(r.email.clone(), r);
+    }
+    latest
+}
+
+/// The tags in `s`, separated by spaces or commas, each without a
+/// leading `#`, and each only once.
+pub fn parse_tags(s: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for t in s.split([' ', ',']) {
+        let t = t.trim().trim_start_matches('#');
+        if !t.is_empty() && !tags.iter().any(|have| have == t) {
+            tags.push(t.to_string());
+        }
+    }
+    tags
+}
@@ -0,0 +1,134 @@
+// Crash-safe writes. Files we rewrite are replaced atomically: a new
+// version is written and synced next to the old one, and only then
+// renamed over it, so a crash or a full disk mid-lecture leaves either
+// the old or the new file, never half of one. The previous versions
+// are kept as a rolling set of timestamped backups. Logs are only ever
+// appended to, and each record is synced as it is written.
+
+use std::{
+    ffi::OsStr,
+    fs::{self, File, OpenOptions},
+    io::Write,
+    marker::PhantomData,
+    path::{Path, PathBuf},
+};
+
+use chrono::Local;
+use serde::{de::DeserializeOwned, Serialize};
+
+/// How many backups of each file to keep around.
+pub const BACKUPS: usize = 10;
+
+/// `path` with `suffix` tacked onto the end of its file name.
+pub fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let mut p = path.as_os_str().to_owned();
+    p.push(suffix);
+    PathBuf::from(p)
+}
+
+// `Path::parent` is empty, not ".", for a bare file name.
+fn dir_of(path: &Path) -> &Path {
+    match path.parent() {
+        Some(d) if d != Path::new("") => d,
+        _ => Path::new("."),
+    }
+}
+
+/// Atomically replace the contents of `path`, backing up what was
+/// there.
+pub fn replace(path: &Path, contents: &[u8]) -> anyhow::Result<()> {
+    let tmp = sibling(path, ".tmp");
+    let mut f = File::create(&tmp)?;
+    f.write_all(contents)?;
+    f.sync_all()?;
+    drop(f);
+
+    if path.exists() {
+        backup(path)?;
+    }
+    fs::rename(&tmp, path)?;
+    // The rename is only durable once the directory is synced.
+    File::open(dir_of(path))?.sync_all()?;
+
+    Ok(())
+}
+
+fn backup(path: &Path) -> anyhow::Result<()> {
+    let stamp = Local::now().format("%Y%m%dT%H%M%S%.3f");
+    fs::copy(path, sibling(path, &format!(".bak.{stamp}")))?;
+
+    // The timestamps sort lexically, so the oldest backups come first.
+    let prefix = sibling(
+        Path::new(path.file_name().unwrap_or(OsStr::new(""))),
+        ".bak.",
+    );
+    let prefix = prefix.to_string_lossy();
+    let mut backups: Vec<PathBuf> = fs::read_dir(dir_of(path))?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| {
+            p.file_name()
+                .map(|n| n.to_string_lossy().starts_with(prefix.as_ref()))
+                .unwrap_or(false)
+        })
+        .collect();
+    backups.sort();
+    let stale = backups.len().saturating_sub(BACKUPS);
+    for old in &backups[..stale] {
+        fs::remove_file(old)?;
+    }
+
+    Ok(())
+}
+
+/// A tab-delimited file of records that is only ever appended to.
+pub struct Log<T> {
+    path: PathBuf,
+    records: PhantomData<T>,
+}
+
+impl<T: Serialize + DeserializeOwned> Log<T> {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            records: PhantomData,
+        }
+    }
+
+    /// All records in the log, in the order they were written. A log
+    /// that doesn't exist yet is simply empty.
+    pub fn read(&self) -> anyhow::Result<Vec<T>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+        let mut r = csv::ReaderBuilder::new()
+            .delimiter(b'\t')
+            .comment(Some(b'#'))
+            .from_reader(File::open(&self.path)?);
+
+        let mut records = Vec::new();
+        for e in r.deserialize() {
+            records.push(e?);
+        }
+        Ok(records)
+    }
+
+    pub fn append(&self, record: &T) -> anyhow::Result<()> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        // Only the first write to the log gets the header.
+        let fresh = file.metadata()?.len() == 0;
+        let mut w = csv::WriterBuilder::new()
+            .has_headers(fresh)
+            .delimiter(b'\t')
+            .from_writer(file);
+        w.serialize(record)?;
+        w.flush()?;
+        // A log is a record of truth, so make sure it hits the disk.
+        w.get_ref().sync_data()?;
+
+        Ok(())
+    }
+}
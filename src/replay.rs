@@ -0,0 +1,133 @@
+// Replaying the draws of a session, to answer "why was I called three
+// times?". A session can be replayed whether or not it has been
+// committed since: starting from the roster it was logged against, as
+// of the commit before it, the events logged before each draw are
+// applied, and the draw's strategy and seed order the students who
+// were in the room, just as in class. Anyone who was cooling down is
+// moved to the end, and marked as such, and below them anyone the
+// roster has exempt from cold-calling. Students who want a warning
+// first get their heads up, or are moved to the top once they have had
+// it, going by who had had one as of the draw.
+
+use std::collections::{BTreeMap, HashSet};
+
+use crate::{
+    calling,
+    cooldown::{self, Cooldown},
+    selection::{self, Draw, SelectionStrategy},
+    store::{Archive, Store},
+    StudentKey,
+};
+
+/// A draw as it is replayed.
+#[derive(Debug, Clone)]
+pub struct Replayed {
+    pub draw: Draw,
+    /// Everyone who was in the room, first to be called first
+    pub order: Vec<Placed>,
+}
+
+/// A student's place in a replayed order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Placed {
+    pub email: StudentKey,
+    pub name: String,
+    /// Moved to the end for having been called too recently
+    pub cooling: bool,
+    /// Moved below everyone else for not being cold-called
+    pub exempt: bool,
+    /// Given a heads up that they are next
+    pub heads_up: bool,
+}
+
+/// The sessions that can be replayed, committed or not, with how many
+/// draws each made.
+pub fn sessions(store: &dyn Store) -> anyhow::Result<BTreeMap<String, usize>> {
+    let mut sessions: BTreeMap<String, usize> = BTreeMap::new();
+    let archived = store.archives()?.into_iter().flat_map(|a| a.draws);
+    for d in archived.chain(store.draws()?) {
+        *sessions.entry(d.session).or_default() += 1;
+    }
+    Ok(sessions)
+}
+
+/// Every order `session` drew, in the order it drew them.
+pub fn replay(store: &dyn Store, session: &str) -> anyhow::Result<Vec<Replayed>> {
+    // The session was either folded into one of the archives, or is
+    // yet to be committed.
+    let pending = Archive {
+        roster: store.load()?,
+        events: store.events()?,
+        draws: store.draws()?,
+    };
+    let Some(archive) = store
+        .archives()?
+        .into_iter()
+        .chain([pending])
+        .find(|a| a.draws.iter().any(|d| d.session == session))
+    else {
+        anyhow::bail!("No draws recorded for session {session}.");
+    };
+    let mut students = archive.roster;
+    let history = store.history()?;
+    let mut events = archive.events.into_iter().peekable();
+    let draws = archive.draws.into_iter().filter(|d| d.session == session);
+
+    // The strategy is kept across draws, as it was in class, so that
+    // the ones with state (round-robin) come out the same.
+    let mut strategy: Option<Box<dyn SelectionStrategy>> = None;
+    let mut replayed = Vec::new();
+    for d in draws {
+        while let Some(e) = events.next_if(|e| e.timestamp <= d.timestamp) {
+            if let Some(s) = students.get_mut(&e.email) {
+                s.apply(&e, session);
+            }
+        }
+        for s in students.values_mut() {
+            s.history = history
+                .iter()
+                .filter(|e| e.email == s.email && e.timestamp <= d.timestamp)
+                .cloned()
+                .collect();
+        }
+        let strategy = match &mut strategy {
+            Some(s) if s.spec() == d.strategy => s,
+            s => s.insert(selection::by_name(&d.strategy)?),
+        };
+
+        let excluded: HashSet<&str> = d.excluded.split_whitespace().collect();
+        let order = selection::draw(
+            strategy.as_mut(),
+            students
+                .values()
+                .filter(|s| !excluded.contains(s.email.as_str())),
+            d.seed,
+        );
+        let cooling = match d.cooldown.as_str() {
+            "" => HashSet::new(),
+            c => cooldown::cooling(
+                students.values(),
+                session,
+                d.timestamp,
+                c.parse::<Cooldown>()?,
+            ),
+        };
+        let order = calling::demote(cooldown::demote(order, &cooling), &students);
+        let mut warned: HashSet<StudentKey> =
+            d.warned.split_whitespace().map(str::to_string).collect();
+        let (order, heads_up) = calling::warn(order, &students, &cooling, &mut warned);
+
+        let order = order
+            .into_iter()
+            .map(|k| Placed {
+                name: students[&k].name.clone(),
+                cooling: cooling.contains(&k),
+                exempt: !students[&k].calling.cold_called(),
+                heads_up: heads_up.as_ref() == Some(&k),
+                email: k,
+            })
+            .collect();
+        replayed.push(Replayed { draw: d, order });
+    }
+    Ok(replayed)
+}
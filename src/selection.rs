@@ -0,0 +1,468 @@
+// How the order of students to call on is chosen. Each strategy is
+// handed the students who are in the room, and returns all of their
+// keys, first to be called first.
+
+use std::{collections::BTreeSet, fmt, ops::Bound, str::FromStr};
+
+use chrono::{DateTime, Local};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, RngCore, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::{Student, StudentKey};
+
+pub trait SelectionStrategy {
+    /// The name used on the command line and in the UI.
+    fn name(&self) -> &'static str;
+    /// The name along with any parameters, which `by_name` parses back
+    /// into the same strategy.
+    fn spec(&self) -> String {
+        self.name().to_string()
+    }
+    fn order(&mut self, students: &[&Student], rng: &mut dyn RngCore) -> Vec<StudentKey>;
+}
+
+/// A re-randomization, recorded so the order it produced can be
+/// replayed: the same strategy, seed, and students give the same order.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Draw {
+    pub timestamp: DateTime<Local>,
+    pub session: String,
+    pub strategy: String,
+    pub seed: u64,
+    /// Students who weren't in the room, space separated
+    pub excluded: String,
+    /// The cooldown that was applied to the order, if any
+    pub cooldown: String,
+    /// Students who had already had their heads up, space separated
+    pub warned: String,
+}
+
+/// Order `students` with an RNG seeded by `seed`. The students are
+/// handed over sorted by key, so nothing depends on hash order.
+pub fn draw<'a>(
+    strategy: &mut dyn SelectionStrategy,
+    students: impl Iterator<Item = &'a Student>,
+    seed: u64,
+) -> Vec<StudentKey> {
+    let mut students: Vec<&Student> = students.collect();
+    students.sort_by(|a, b| a.email.cmp(&b.email));
+    strategy.order(&students, &mut StdRng::seed_from_u64(seed))
+}
+
+/// All strategies, in the order the UI cycles through them.
+pub const STRATEGIES: [&str; 6] = [
+    "biased",
+    "uniform",
+    "round-robin",
+    "least-recent",
+    "lowest",
+    "decayed",
+];
+
+/// The strategy for a spec: its name, and for `decayed` an optional
+/// `:half-life`, e.g. `decayed:14d` or `decayed:3sessions`.
+pub fn by_name(spec: &str) -> anyhow::Result<Box<dyn SelectionStrategy>> {
+    let (name, param) = match spec.split_once(':') {
+        Some((name, param)) => (name, Some(param)),
+        None => (spec, None),
+    };
+    match (name, param) {
+        ("biased", None) => Ok(Box::new(Biased)),
+        ("uniform", None) => Ok(Box::new(Uniform)),
+        ("round-robin", None) => Ok(Box::new(RoundRobin::default())),
+        ("least-recent", None) => Ok(Box::new(LeastRecent)),
+        ("lowest", None) => Ok(Box::new(Lowest)),
+        ("decayed", None) => Ok(Box::new(Decayed {
+            half_life: HalfLife::Days(14.0),
+        })),
+        ("decayed", Some(h)) => Ok(Box::new(Decayed {
+            half_life: h.parse()?,
+        })),
+        _ => anyhow::bail!(
+            "Unknown selection strategy \"{spec}\", expected one of {}.",
+            STRATEGIES.join(", ")
+        ),
+    }
+}
+
+/// The strategy after `current`, wrapping around. `configured` is the
+/// spec the user asked for, which is used again when its turn comes.
+pub fn next(current: &str, configured: &str) -> Box<dyn SelectionStrategy> {
+    let i = STRATEGIES.iter().position(|s| *s == current).unwrap_or(0);
+    let name = STRATEGIES[(i + 1) % STRATEGIES.len()];
+    match by_name(configured) {
+        Ok(s) if s.name() == name => s,
+        _ => by_name(name).expect("STRATEGIES has an unknown name"),
+    }
+}
+
+// Efraimidis-Spirakis: weight each student by how far their score is
+// below the highest, plus one, give each the key u^(1/w) for a uniform
+// u, and sort by key, largest first. The logarithm of the key orders
+// the same and doesn't underflow for large weights.
+fn weighted(students: &[&Student], scores: &[f64], rng: &mut dyn RngCore) -> Vec<StudentKey> {
+    let max = scores.iter().copied().fold(f64::MIN, f64::max);
+
+    let mut keyed: Vec<(f64, &Student)> = students
+        .iter()
+        .zip(scores)
+        .map(|(s, score)| {
+            let w = max - score + 1.0;
+            // In (0, 1], so the logarithm is finite.
+            let u: f64 = 1.0 - rng.gen::<f64>();
+            (u.ln() / w, *s)
+        })
+        .collect();
+    keyed.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+
+    keyed.into_iter().map(|(_, s)| s.email.clone()).collect()
+}
+
+// Shuffle first so that ties in a stable sort are broken randomly.
+fn shuffled(students: &[&Student], rng: &mut dyn RngCore) -> Vec<StudentKey> {
+    let mut keys: Vec<StudentKey> = students.iter().map(|s| s.email.clone()).collect();
+    keys.shuffle(rng);
+    keys
+}
+
+/// Random, biased towards students with a lower participation score
+/// (less deferrals). Each student is weighted by how far their score is
+/// below the highest, plus one, and the order is a weighted sample
+/// without replacement. This is the same distribution as shuffling a
+/// bag with that many tokens per student and taking the order in which
+/// each student's first token turns up, which is how it used to work.
+pub struct Biased;
+
+impl SelectionStrategy for Biased {
+    fn name(&self) -> &'static str {
+        "biased"
+    }
+
+    fn order(&mut self, students: &[&Student], rng: &mut dyn RngCore) -> Vec<StudentKey> {
+        let scores: Vec<f64> = students.iter().map(|s| s.score() as f64).collect();
+        weighted(students, &scores, rng)
+    }
+}
+
+/// Every order is equally likely, regardless of history.
+pub struct Uniform;
+
+impl SelectionStrategy for Uniform {
+    fn name(&self) -> &'static str {
+        "uniform"
+    }
+
+    fn order(&mut self, students: &[&Student], rng: &mut dyn RngCore) -> Vec<StudentKey> {
+        shuffled(students, rng)
+    }
+}
+
+/// A random order that stays put while it is worked through: whoever
+/// has been called since the round started drops to the end, and once
+/// everyone has been called a new round starts with a fresh order.
+#[derive(Default)]
+pub struct RoundRobin {
+    round: Vec<StudentKey>,
+    /// Has the first round started?
+    started: bool,
+    /// The most recent call before the round started. This, rather
+    /// than the clock, marks the start so that replays match.
+    since: Option<DateTime<Local>>,
+}
+
+impl SelectionStrategy for RoundRobin {
+    fn name(&self) -> &'static str {
+        "round-robin"
+    }
+
+    fn order(&mut self, students: &[&Student], rng: &mut dyn RngCore) -> Vec<StudentKey> {
+        let called = |s: &Student, since: Option<DateTime<Local>>| match (s.last_called(), since) {
+            (Some(c), Some(since)) => c > since,
+            (called, None) => called.is_some(),
+            (None, _) => false,
+        };
+        if !self.started || students.iter().all(|s| called(s, self.since)) {
+            self.round = shuffled(students, rng);
+            self.started = true;
+            self.since = students.iter().filter_map(|s| s.last_called()).max();
+        }
+        // Anyone who arrived since the round started joins its end.
+        for s in students {
+            if !self.round.contains(&s.email) {
+                self.round.push(s.email.clone());
+            }
+        }
+
+        // And anyone who has left is skipped.
+        let (done, todo): (Vec<&Student>, Vec<&Student>) = self
+            .round
+            .iter()
+            .filter_map(|k| students.iter().find(|s| s.email == *k).copied())
+            .partition(|s| called(s, self.since));
+        todo.into_iter()
+            .chain(done)
+            .map(|s| s.email.clone())
+            .collect()
+    }
+}
+
+/// Whoever was called on longest ago (or never) goes first.
+pub struct LeastRecent;
+
+impl SelectionStrategy for LeastRecent {
+    fn name(&self) -> &'static str {
+        "least-recent"
+    }
+
+    fn order(&mut self, students: &[&Student], rng: &mut dyn RngCore) -> Vec<StudentKey> {
+        let mut order: Vec<&Student> = students.to_vec();
+        order.shuffle(rng);
+        // `None` sorts before any time, so the never-called come first.
+        order.sort_by_key(|s| s.last_called());
+        order.into_iter().map(|s| s.email.clone()).collect()
+    }
+}
+
+/// Strictly the lowest participation score (less deferrals) first.
+pub struct Lowest;
+
+impl SelectionStrategy for Lowest {
+    fn name(&self) -> &'static str {
+        "lowest"
+    }
+
+    fn order(&mut self, students: &[&Student], rng: &mut dyn RngCore) -> Vec<StudentKey> {
+        let mut order: Vec<&Student> = students.to_vec();
+        order.shuffle(rng);
+        order.sort_by_key(|s| s.score());
+        order.into_iter().map(|s| s.email.clone()).collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HalfLife {
+    Days(f64),
+    Sessions(f64),
+}
+
+impl FromStr for HalfLife {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let split = s.find(|c: char| c.is_alphabetic()).unwrap_or(s.len());
+        let (n, unit) = s.split_at(split);
+        let n: f64 = n.parse()?;
+        if n <= 0.0 {
+            anyhow::bail!("A half-life must be positive, not \"{s}\".");
+        }
+        match unit {
+            "d" | "days" => Ok(HalfLife::Days(n)),
+            "s" | "sessions" => Ok(HalfLife::Sessions(n)),
+            _ => anyhow::bail!("Unknown half-life \"{s}\", expected e.g. 14d or 3sessions."),
+        }
+    }
+}
+
+impl fmt::Display for HalfLife {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HalfLife::Days(n) => write!(f, "{n}d"),
+            HalfLife::Sessions(n) => write!(f, "{n}sessions"),
+        }
+    }
+}
+
+/// Like `biased`, but each answer (or deferral) counts for less the
+/// longer ago it was, halving every `half_life`. Students who did most
+/// of their talking early in the semester get pulled back in.
+/// Participation from before the event log began has fully decayed.
+pub struct Decayed {
+    half_life: HalfLife,
+}
+
+impl SelectionStrategy for Decayed {
+    fn name(&self) -> &'static str {
+        "decayed"
+    }
+
+    fn spec(&self) -> String {
+        format!("decayed:{}", self.half_life)
+    }
+
+    fn order(&mut self, students: &[&Student], rng: &mut dyn RngCore) -> Vec<StudentKey> {
+        let scores = self.scores(students);
+        weighted(students, &scores, rng)
+    }
+}
+
+impl Decayed {
+    // Ages are measured from the latest event anyone has, rather than
+    // the clock, so that replays come out the same.
+    fn scores(&self, students: &[&Student]) -> Vec<f64> {
+        let history = || students.iter().flat_map(|s| s.history.iter());
+        let latest = history().map(|e| e.timestamp).max();
+        let sessions: BTreeSet<&str> = history().map(|e| e.session.as_str()).collect();
+
+        students
+            .iter()
+            .map(|s| {
+                s.history
+                    .iter()
+                    .map(|e| {
+                        let age = match self.half_life {
+                            HalfLife::Days(h) => {
+                                let secs = latest.map_or(0, |l| (l - e.timestamp).num_seconds());
+                                secs as f64 / (24.0 * 60.0 * 60.0) / h
+                            }
+                            HalfLife::Sessions(h) => {
+                                let later = sessions
+                                    .range::<str, _>((
+                                        Bound::Excluded(e.session.as_str()),
+                                        Bound::Unbounded,
+                                    ))
+                                    .count();
+                                later as f64 / h
+                            }
+                        };
+                        e.weight() as f64 * 0.5f64.powf(age)
+                    })
+                    .sum()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, time::Instant};
+
+    use super::*;
+
+    fn student(email: &str, score: usize) -> Student {
+        let mut s = Student::new(email.to_string(), email.to_string());
+        s.participation_score = score;
+        s
+    }
+
+    // Weighted sampling without replacement: each order comes up as
+    // often as picking the first with probability w_i/Σw, the next
+    // from whoever is left, and so on.
+    #[test]
+    fn biased_orders_follow_the_weights() {
+        // Weighted 4, 3, and 1 by how far they are below the highest.
+        let students = [student("a", 0), student("b", 1), student("c", 3)];
+        let weights: HashMap<&str, f64> = [("a", 4.0), ("b", 3.0), ("c", 1.0)].into();
+        let total: f64 = weights.values().sum();
+
+        const DRAWS: u64 = 20_000;
+        let mut orders: HashMap<Vec<StudentKey>, u64> = HashMap::new();
+        for seed in 0..DRAWS {
+            *orders
+                .entry(draw(&mut Biased, students.iter(), seed))
+                .or_default() += 1;
+        }
+        assert_eq!(orders.len(), 6);
+
+        let mut firsts: HashMap<&str, u64> = HashMap::new();
+        for (order, n) in &orders {
+            *firsts.entry(order[0].as_str()).or_default() += n;
+
+            let (first, second) = (weights[order[0].as_str()], weights[order[1].as_str()]);
+            let expected = first / total * second / (total - first);
+            let seen = *n as f64 / DRAWS as f64;
+            assert!(
+                (seen - expected).abs() < 0.015,
+                "{order:?}: {seen} vs {expected}"
+            );
+        }
+        for (email, w) in &weights {
+            let seen = firsts[email] as f64 / DRAWS as f64;
+            let expected = w / total;
+            assert!(
+                (seen - expected).abs() < 0.015,
+                "{email}: {seen} vs {expected}"
+            );
+        }
+    }
+
+    fn answered(s: &mut Student, session: &str, at: &str) {
+        s.history.push(crate::events::Event {
+            timestamp: at.parse().unwrap(),
+            session: session.to_string(),
+            email: s.email.clone(),
+            outcome: "answer".to_string(),
+            undo: false,
+            counter: crate::events::Counter::ParticipationScore,
+            points: 1,
+            weighted: true,
+        });
+    }
+
+    #[test]
+    fn decayed_halves_by_days() {
+        let (mut early, mut late) = (student("early", 0), student("late", 0));
+        answered(&mut early, "1", "2026-09-01T10:00:00+00:00");
+        answered(&mut early, "2", "2026-09-15T10:00:00+00:00");
+        answered(&mut late, "2", "2026-09-15T10:00:00+00:00");
+        answered(&mut late, "3", "2026-09-29T10:00:00+00:00");
+
+        let decayed = |h| Decayed {
+            half_life: HalfLife::Days(h),
+        };
+        // Ages are from the latest event: 28 and 14 days, then 14 and 0.
+        assert_eq!(decayed(14.0).scores(&[&early, &late]), [0.75, 1.5]);
+        assert_eq!(decayed(7.0).scores(&[&early, &late]), [0.3125, 1.25]);
+    }
+
+    #[test]
+    fn decayed_halves_by_sessions() {
+        let (mut early, mut late) = (student("early", 0), student("late", 0));
+        // However far apart they were held.
+        answered(&mut early, "2026-09-01", "2026-09-01T10:00:00+00:00");
+        answered(&mut early, "2026-09-02", "2026-09-02T10:00:00+00:00");
+        answered(&mut late, "2026-09-02", "2026-09-02T10:01:00+00:00");
+        answered(&mut late, "2026-12-01", "2026-12-01T10:00:00+00:00");
+
+        let decayed = |h| Decayed {
+            half_life: HalfLife::Sessions(h),
+        };
+        // One and two sessions later for the early, none and one for the late.
+        assert_eq!(decayed(1.0).scores(&[&early, &late]), [0.75, 1.5]);
+        assert_eq!(
+            decayed(2.0).scores(&[&early, &late]),
+            [0.5f64.sqrt() + 0.5, 1.0 + 0.5f64.sqrt()]
+        );
+    }
+
+    #[test]
+    fn half_lives_parse_back_from_their_spec() {
+        assert_eq!("14d".parse::<HalfLife>().unwrap(), HalfLife::Days(14.0));
+        assert_eq!(
+            "3sessions".parse::<HalfLife>().unwrap(),
+            HalfLife::Sessions(3.0)
+        );
+        assert!("0d".parse::<HalfLife>().is_err());
+        assert!("3w".parse::<HalfLife>().is_err());
+        let spec = by_name("decayed:2.5s").unwrap().spec();
+        assert_eq!(by_name(&spec).unwrap().spec(), spec);
+    }
+
+    // The token bag this replaced grew with the spread of the scores.
+    #[test]
+    fn large_rosters_are_ordered_quickly() {
+        let students: Vec<Student> = (0..500)
+            .map(|i| student(&format!("s{i:03}@x.edu"), i * 1000))
+            .collect();
+        let start = Instant::now();
+        let order = draw(&mut Biased, students.iter(), 7);
+        assert!(start.elapsed().as_secs() < 1);
+
+        let mut sorted = order.clone();
+        sorted.sort();
+        let emails: Vec<StudentKey> = students.iter().map(|s| s.email.clone()).collect();
+        assert_eq!(sorted, emails);
+        // The lowest score is weighted half a million times the highest.
+        let place = |k: &StudentKey| order.iter().position(|o| o == k);
+        assert!(place(&emails[0]) < place(&emails[499]));
+    }
+}
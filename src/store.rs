@@ -0,0 +1,153 @@
+// Where the roster and its events live. A store holds the roster as
+// of the last commit, and the events logged since then; the current
+// counters are always that roster with those events replayed on top.
+// Each commit archives the roster it replaces, along with the events
+// and draws folded into it, so any session can be replayed later.
+
+use std::{
+    collections::HashMap,
+    ffi::OsStr,
+    path::{Path, PathBuf},
+};
+
+use crate::{attendance, events::Event, notes, selection::Draw, Student, StudentKey};
+
+mod csv_file;
+mod sqlite;
+
+pub use csv_file::CsvStore;
+pub use sqlite::SqliteStore;
+
+pub trait Store {
+    /// The roster as of the last commit.
+    fn load(&self) -> anyhow::Result<HashMap<StudentKey, Student>>;
+    /// The events logged since the last commit, in order.
+    fn events(&self) -> anyhow::Result<Vec<Event>>;
+    /// Every event there is a record of, committed or not, in order.
+    fn history(&self) -> anyhow::Result<Vec<Event>>;
+    /// Durably record an event.
+    fn append(&mut self, event: &Event) -> anyhow::Result<()>;
+    /// Write out the current counters for anyone reading the store.
+    fn save(&mut self, students: &HashMap<StudentKey, Student>) -> anyhow::Result<()>;
+    /// Replace the committed roster itself, leaving pending events be.
+    fn write_roster(&mut self, students: &HashMap<StudentKey, Student>) -> anyhow::Result<()>;
+    /// Fold the pending events into the roster.
+    fn commit(&mut self) -> anyhow::Result<()>;
+    /// Every attendance record, oldest first. Attendance isn't
+    /// folded into the roster, so commits leave it be.
+    fn attendance(&self) -> anyhow::Result<Vec<attendance::Record>>;
+    /// Durably record a student's attendance.
+    fn record_attendance(&mut self, record: &attendance::Record) -> anyhow::Result<()>;
+    /// Every edit of students' notes and tags, oldest first. Like
+    /// attendance, they are kept apart from the roster.
+    fn notes(&self) -> anyhow::Result<Vec<notes::Record>>;
+    /// Durably record a student's notes and tags.
+    fn record_notes(&mut self, record: &notes::Record) -> anyhow::Result<()>;
+    /// The draws since the last commit, in order.
+    fn draws(&self) -> anyhow::Result<Vec<Draw>>;
+    fn record_draw(&mut self, draw: &Draw) -> anyhow::Result<()>;
+    /// The rosters that commits replaced, oldest first, each with what
+    /// was folded into it.
+    fn archives(&self) -> anyhow::Result<Vec<Archive>>;
+    /// Keep a commit made elsewhere, e.g. in a store being converted,
+    /// as the newest archive.
+    fn archive(&mut self, archive: &Archive) -> anyhow::Result<()>;
+}
+
+/// A roster as a commit found it, with the events and draws that
+/// were logged on top of it, and then folded in.
+#[derive(Debug, Clone, Default)]
+pub struct Archive {
+    pub roster: HashMap<StudentKey, Student>,
+    pub events: Vec<Event>,
+    pub draws: Vec<Draw>,
+}
+
+/// The current counters: the roster as of the last commit, with the
+/// events logged since applied on top. Answers in `session` count as
+/// today's.
+pub fn current(store: &dyn Store, session: &str) -> anyhow::Result<HashMap<StudentKey, Student>> {
+    let mut students = store.load()?;
+    for e in store.events()? {
+        // Students removed from the roster keep their history in
+        // the log, but there is nothing left to apply it to.
+        if let Some(s) = students.get_mut(&e.email) {
+            s.apply(&e, session);
+        }
+    }
+    Ok(students)
+}
+
+/// Copy everything in `from` into `to`, which is meant to be empty:
+/// the archives first, so the whole history comes along and stays
+/// committed, then the roster with the events and draws pending on
+/// it, and the attendance and notes.
+pub fn copy(from: &dyn Store, to: &mut dyn Store) -> anyhow::Result<()> {
+    for a in from.archives()? {
+        to.archive(&a)?;
+    }
+    to.write_roster(&from.load()?)?;
+    for e in from.events()? {
+        to.append(&e)?;
+    }
+    for d in from.draws()? {
+        to.record_draw(&d)?;
+    }
+    for a in from.attendance()? {
+        to.record_attendance(&a)?;
+    }
+    for n in from.notes()? {
+        to.record_notes(&n)?;
+    }
+    // So that what is pending can be committed.
+    if !to.events()?.is_empty() {
+        to.save(&current(to, "")?)?;
+    }
+    Ok(())
+}
+
+/// How to open a store. The output and delimiter only apply to CSV.
+#[derive(Debug, Clone)]
+pub struct Options {
+    /// `csv` or `sqlite`, rather than going by the file extension
+    pub kind: Option<String>,
+    /// Where the session's output goes, rather than `<roster>.out`
+    pub output: Option<PathBuf>,
+    /// What separates the fields of the roster and the output
+    pub delimiter: u8,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            kind: None,
+            output: None,
+            delimiter: b'\t',
+        }
+    }
+}
+
+/// Open the store at `path`. Its kind is either given (`csv` or
+/// `sqlite`), or taken from the file extension.
+pub fn open(path: &OsStr, kind: Option<&OsStr>) -> anyhow::Result<Box<dyn Store>> {
+    let options = Options {
+        kind: kind.map(|k| k.to_string_lossy().into_owned()),
+        ..Options::default()
+    };
+    open_with(path, &options)
+}
+
+pub fn open_with(path: &OsStr, options: &Options) -> anyhow::Result<Box<dyn Store>> {
+    let kind = match &options.kind {
+        Some(k) => k.clone(),
+        None => match Path::new(path).extension().and_then(|e| e.to_str()) {
+            Some("db" | "sqlite" | "sqlite3") => "sqlite".to_string(),
+            _ => "csv".to_string(),
+        },
+    };
+    match kind.as_str() {
+        "csv" => Ok(Box::new(CsvStore::new(path, options))),
+        "sqlite" => Ok(Box::new(SqliteStore::open(path)?)),
+        k => anyhow::bail!("Unknown store \"{k}\", expected csv or sqlite."),
+    }
+}
@@ -0,0 +1,456 @@
+// The original store: a tab-delimited roster, the session's output
+// written next to it as `<roster>.out`, and the events in
+// `<roster>.log`, with the draws that ordered the students in
+// `<roster>.draws`. Committing a session makes the output the new
+// roster, and archives the old roster, along with the logs of the
+// events and draws folded into it, as `<roster>.<stamp>`, where the
+// stamp is the date. The archives are read back for the history, and
+// to replay the sessions in them. This used to live in
+// `particip.sh rotate`. Attendance is kept in `<roster>.attendance`,
+// which spans the whole semester, as are the instructor's notes on
+// students in `<roster>.notes`. The roster's delimiter and where the
+// output goes can be configured; the logs are always tab-delimited and
+// next to the roster. The roster's first line gives the version of its
+// format, so older rosters can still be read.
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    ffi::OsStr,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Local};
+
+use super::{Archive, Options, Store};
+use crate::{
+    attendance,
+    events::{Event, EventLog},
+    notes, persist,
+    selection::Draw,
+    Student, StudentKey,
+};
+
+pub struct CsvStore {
+    roster: PathBuf,
+    out: PathBuf,
+    delimiter: u8,
+    log: EventLog,
+    draws: persist::Log<Draw>,
+    attendance: persist::Log<attendance::Record>,
+    notes: persist::Log<notes::Record>,
+}
+
+impl CsvStore {
+    pub fn new(roster: &OsStr, options: &Options) -> Self {
+        let roster = PathBuf::from(roster);
+        let out = options
+            .output
+            .clone()
+            .unwrap_or_else(|| persist::sibling(&roster, ".out"));
+        let log = EventLog::new(persist::sibling(&roster, ".log"));
+        let draws = persist::Log::new(persist::sibling(&roster, ".draws"));
+        let attendance = persist::Log::new(persist::sibling(&roster, ".attendance"));
+        let notes = persist::Log::new(persist::sibling(&roster, ".notes"));
+        Self {
+            roster,
+            out,
+            delimiter: options.delimiter,
+            log,
+            draws,
+            attendance,
+            notes,
+        }
+    }
+}
+
+/// The roster's columns in each version of its format, oldest first. A
+/// roster says which it is in a first line like `# participation roster
+/// v2`; one without is taken for whichever version its columns match.
+/// Columns added since a version must have serde defaults, so that
+/// rosters of that version still read, and are then written in the
+/// current one.
+const VERSIONS: [&[&str]; 3] = [
+    &[
+        "name",
+        "email",
+        "participation_score",
+        "deferrals",
+        "absent",
+    ],
+    &[
+        "name",
+        "email",
+        "participation_score",
+        "deferrals",
+        "absent",
+        "inactive",
+    ],
+    &[
+        "name",
+        "email",
+        "participation_score",
+        "deferrals",
+        "absent",
+        "inactive",
+        "calling",
+    ],
+];
+
+const VERSION_PREFIX: &str = "# participation roster v";
+
+pub fn deserialize_file(
+    file_path: &Path,
+    delimiter: u8,
+) -> anyhow::Result<HashMap<StudentKey, Student>> {
+    let text = fs::read_to_string(file_path)
+        .map_err(|e| anyhow::anyhow!("Could not read {}: {e}", file_path.display()))?;
+
+    let mut f = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .comment(Some(b'#'))
+        .from_reader(text.as_bytes());
+    let headers: Vec<String> = f.headers()?.iter().map(|h| h.trim().to_string()).collect();
+    let version = version(file_path, text.lines().next(), &headers)?;
+    check_columns(file_path, version, &headers)?;
+
+    let mut students = HashMap::new();
+    for s_rec in f.deserialize() {
+        let s: Student = s_rec.map_err(|e| anyhow::anyhow!("{}: {e}", file_path.display()))?;
+        let email = s.email.trim().to_string();
+
+        students.insert(
+            email.clone(),
+            Student {
+                participation_score: s.participation_score,
+                deferrals: s.deferrals,
+                absent: s.absent,
+                inactive: s.inactive,
+                calling: s.calling,
+                ..Student::new(s.name.trim().to_string(), email)
+            },
+        );
+    }
+
+    Ok(students)
+}
+
+// The version of the roster at `path`, from its `first` line, or else
+// the one its `headers` are closest to.
+fn version(path: &Path, first: Option<&str>, headers: &[String]) -> anyhow::Result<usize> {
+    if let Some(v) = first.and_then(|l| l.trim().strip_prefix(VERSION_PREFIX)) {
+        return match v.parse::<usize>() {
+            Ok(v) if (1..=VERSIONS.len()).contains(&v) => Ok(v),
+            Ok(v) if v > VERSIONS.len() => anyhow::bail!(
+                "{} is a version {v} roster, but this version of participation only \
+                 reads up to version {}; it needs upgrading.",
+                path.display(),
+                VERSIONS.len()
+            ),
+            _ => anyhow::bail!(
+                "{} has an unrecognized version line \"{}\".",
+                path.display(),
+                first.unwrap_or_default().trim()
+            ),
+        };
+    }
+    let differences = |columns: &[&str]| {
+        let missing = columns.iter().filter(|c| !headers.iter().any(|h| h == *c));
+        let unknown = headers.iter().filter(|h| !columns.contains(&h.as_str()));
+        missing.count() + unknown.count()
+    };
+    // Ties go to the newest version.
+    Ok(VERSIONS
+        .iter()
+        .enumerate()
+        .rev()
+        .min_by_key(|(_, columns)| differences(columns))
+        .map_or(VERSIONS.len(), |(i, _)| i + 1))
+}
+
+// Every column the `version` has must be there, and nothing else.
+fn check_columns(path: &Path, version: usize, headers: &[String]) -> anyhow::Result<()> {
+    let columns = VERSIONS[version - 1];
+    let missing: Vec<&str> = columns
+        .iter()
+        .filter(|c| !headers.iter().any(|h| h == *c))
+        .copied()
+        .collect();
+    let unknown: Vec<&str> = headers
+        .iter()
+        .filter(|h| !columns.contains(&h.as_str()))
+        .map(String::as_str)
+        .collect();
+
+    let mut problems = Vec::new();
+    if !missing.is_empty() {
+        problems.push(format!("missing {}", missing.join(", ")));
+    }
+    if !unknown.is_empty() {
+        problems.push(format!("unknown {}", unknown.join(", ")));
+    }
+    if !problems.is_empty() {
+        // All of the columns run together when the delimiter is wrong.
+        let hint = if headers.len() == 1 {
+            " Is the delimiter right?"
+        } else {
+            ""
+        };
+        anyhow::bail!(
+            "{} doesn't have the columns of a version {version} roster ({}): {}.{hint}",
+            path.display(),
+            columns.join(", "),
+            problems.join("; ")
+        );
+    }
+    Ok(())
+}
+
+// Always in the current version, whatever it was read as.
+fn serialize_file(
+    path: &Path,
+    students: &HashMap<StudentKey, Student>,
+    delimiter: u8,
+) -> anyhow::Result<()> {
+    let contents = format!("{VERSION_PREFIX}{}\n", VERSIONS.len()).into_bytes();
+    let mut writer = csv::WriterBuilder::new()
+        .has_headers(false)
+        .delimiter(delimiter)
+        .from_writer(contents);
+
+    // Written out by hand so that even an empty roster has them.
+    writer.write_record(VERSIONS[VERSIONS.len() - 1])?;
+    for s in students.values() {
+        writer.serialize(s)?;
+    }
+
+    let contents = writer.into_inner()?;
+    persist::replace(path, &contents)
+}
+
+impl Store for CsvStore {
+    fn load(&self) -> anyhow::Result<HashMap<StudentKey, Student>> {
+        deserialize_file(&self.roster, self.delimiter)
+    }
+
+    fn events(&self) -> anyhow::Result<Vec<Event>> {
+        self.log.read()
+    }
+
+    // The logs of earlier sessions sit next to the archived rosters
+    // they were folded into, as `<roster>.<stamp>.log`.
+    fn history(&self) -> anyhow::Result<Vec<Event>> {
+        let mut events = self.events()?;
+        for stamp in self.stamps()? {
+            events.extend(EventLog::new(self.archived(&stamp, ".log")).read()?);
+        }
+        events.sort_by_key(|e| e.timestamp);
+        Ok(events)
+    }
+
+    fn append(&mut self, event: &Event) -> anyhow::Result<()> {
+        self.log.append(event)
+    }
+
+    fn save(&mut self, students: &HashMap<StudentKey, Student>) -> anyhow::Result<()> {
+        serialize_file(&self.out, students, self.delimiter)
+    }
+
+    fn write_roster(&mut self, students: &HashMap<StudentKey, Student>) -> anyhow::Result<()> {
+        serialize_file(&self.roster, students, self.delimiter)
+    }
+
+    fn commit(&mut self) -> anyhow::Result<()> {
+        let roster = &self.roster;
+        let out = &self.out;
+
+        if !out.exists() {
+            anyhow::bail!("Nothing to commit: {} does not exist.", out.display());
+        }
+        if fs::metadata(out)?.modified()? < fs::metadata(roster)?.modified()? {
+            anyhow::bail!(
+                "{} is older than {}; refusing to replace the roster with stale output.",
+                out.display(),
+                roster.display()
+            );
+        }
+        self.check_consistent(out)?;
+
+        // The old roster is copied rather than moved, so that there is
+        // a roster throughout, and the output replaces it in one rename,
+        // last. If any step fails, the ones before it are undone.
+        let archive = self.archived(&self.stamp(Local::now())?, "");
+        let mut moved = Vec::new();
+        let result = (|| {
+            fs::copy(roster, &archive)?;
+            for log in [".log", ".draws"] {
+                let path = persist::sibling(roster, log);
+                if path.exists() {
+                    let to = persist::sibling(&archive, log);
+                    fs::rename(&path, &to)?;
+                    moved.push((path, to));
+                }
+            }
+            fs::rename(out, roster)
+        })();
+        if let Err(e) = result {
+            for (from, to) in moved.iter().rev() {
+                let _ = fs::rename(to, from);
+            }
+            let _ = fs::remove_file(&archive);
+            anyhow::bail!("Could not commit, so the roster is as it was: {e}");
+        }
+
+        Ok(())
+    }
+
+    fn attendance(&self) -> anyhow::Result<Vec<attendance::Record>> {
+        self.attendance.read()
+    }
+
+    fn record_attendance(&mut self, record: &attendance::Record) -> anyhow::Result<()> {
+        self.attendance.append(record)
+    }
+
+    fn notes(&self) -> anyhow::Result<Vec<notes::Record>> {
+        self.notes.read()
+    }
+
+    fn record_notes(&mut self, record: &notes::Record) -> anyhow::Result<()> {
+        self.notes.append(record)
+    }
+
+    fn draws(&self) -> anyhow::Result<Vec<Draw>> {
+        self.draws.read()
+    }
+
+    fn record_draw(&mut self, draw: &Draw) -> anyhow::Result<()> {
+        self.draws.append(draw)
+    }
+
+    fn archives(&self) -> anyhow::Result<Vec<Archive>> {
+        let mut archives = Vec::new();
+        for stamp in self.stamps()? {
+            archives.push(Archive {
+                roster: deserialize_file(&self.archived(&stamp, ""), self.delimiter)?,
+                events: EventLog::new(self.archived(&stamp, ".log")).read()?,
+                draws: persist::Log::new(self.archived(&stamp, ".draws")).read()?,
+            });
+        }
+        Ok(archives)
+    }
+
+    // Stamped with the time of the latest thing in it, so that it
+    // sorts among the others as if it had been committed here.
+    fn archive(&mut self, archive: &Archive) -> anyhow::Result<()> {
+        let latest = archive
+            .events
+            .iter()
+            .map(|e| e.timestamp)
+            .chain(archive.draws.iter().map(|d| d.timestamp))
+            .max();
+        let stamp = self.stamp(latest.unwrap_or_else(Local::now))?;
+        serialize_file(&self.archived(&stamp, ""), &archive.roster, self.delimiter)?;
+        let log = EventLog::new(self.archived(&stamp, ".log"));
+        for e in &archive.events {
+            log.append(e)?;
+        }
+        let draws = persist::Log::new(self.archived(&stamp, ".draws"));
+        for d in &archive.draws {
+            draws.append(d)?;
+        }
+        Ok(())
+    }
+}
+
+impl CsvStore {
+    // The archived roster with `stamp`, or its log with `suffix`.
+    fn archived(&self, stamp: &str, suffix: &str) -> PathBuf {
+        persist::sibling(&self.roster, &format!(".{stamp}{suffix}"))
+    }
+
+    // The stamps of the archives, oldest first. Backups, the output,
+    // and the semester-long logs sit next to the roster too, but only
+    // an archive's stamp is a date, and has no dots.
+    fn stamps(&self) -> anyhow::Result<BTreeSet<String>> {
+        let name = self
+            .roster
+            .file_name()
+            .map(|n| format!("{}.", n.to_string_lossy()))
+            .unwrap_or_default();
+        let dir = match self.roster.parent() {
+            Some(d) if d != Path::new("") => d,
+            _ => Path::new("."),
+        };
+
+        let mut stamps = BTreeSet::new();
+        for entry in fs::read_dir(dir)? {
+            let file = entry?.file_name().to_string_lossy().into_owned();
+            let Some(rest) = file.strip_prefix(&name) else {
+                continue;
+            };
+            let stamp = [".log", ".draws"]
+                .iter()
+                .find_map(|log| rest.strip_suffix(log))
+                .unwrap_or(rest);
+            if stamp.starts_with(|c: char| c.is_ascii_digit()) && !stamp.contains('.') {
+                stamps.insert(stamp.to_string());
+            }
+        }
+        Ok(stamps)
+    }
+
+    // A stamp for an archive made at `at`: its date, unless that would
+    // clobber an earlier archive from the same day.
+    fn stamp(&self, at: DateTime<Local>) -> anyhow::Result<String> {
+        let stamp = at.format("%Y-%m-%d").to_string();
+        if self.stamps()?.contains(&stamp) {
+            return Ok(at.format("%Y-%m-%dT%H%M%S").to_string());
+        }
+        Ok(stamp)
+    }
+
+    // The output must be exactly the roster with the log replayed on
+    // top of it. Anything else means one of them was edited or
+    // clobbered, and promoting the output would silently lose or
+    // invent participation.
+    fn check_consistent(&self, out: &Path) -> anyhow::Result<()> {
+        let expected = super::current(self, "")?;
+        let actual = deserialize_file(out, self.delimiter)?;
+
+        let mut problems = Vec::new();
+        for (k, e) in &expected {
+            match actual.get(k) {
+                None => problems.push(format!("{k} is missing from the output")),
+                Some(a)
+                    if (a.participation_score, a.deferrals, a.absent)
+                        != (e.participation_score, e.deferrals, e.absent) =>
+                {
+                    problems.push(format!(
+                        "{k} has {}/{}/{} but the roster and log give {}/{}/{}",
+                        a.participation_score,
+                        a.deferrals,
+                        a.absent,
+                        e.participation_score,
+                        e.deferrals,
+                        e.absent
+                    ))
+                }
+                Some(_) => {}
+            }
+        }
+        for k in actual.keys().filter(|k| !expected.contains_key(*k)) {
+            problems.push(format!("{k} is in the output but not the roster"));
+        }
+
+        if !problems.is_empty() {
+            problems.sort();
+            anyhow::bail!(
+                "{} is inconsistent with the roster and its log:\n  {}",
+                out.display(),
+                problems.join("\n  ")
+            );
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,485 @@
+// An embedded SQLite store, so a whole semester's history lives in one
+// file that other tools can query. It mirrors the CSV store: the
+// `students` table is the roster as of the last commit, and `events`
+// holds every event, tagged with its session. Sessions that haven't
+// been committed yet are replayed on top of the roster, and the
+// `totals` view does the same for anyone reading the file directly.
+// Each commit has a row in `commits`, the roster it replaced is kept
+// in `archive`, and the sessions folded in point at it, so they can
+// be replayed. `attendance` has a row per student per date, `notes` a
+// row per edit of a student's notes and tags, and `draws` a row per
+// re-randomization.
+
+use std::{collections::HashMap, ffi::OsStr, str::FromStr};
+
+use chrono::{DateTime, Local};
+use rusqlite::{params, Connection, Params};
+
+use super::{Archive, Store};
+use crate::{
+    attendance,
+    calling::Calling,
+    events::{Counter, Event},
+    notes,
+    selection::Draw,
+    Student, StudentKey,
+};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS students (
+    email TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    participation_score INTEGER NOT NULL DEFAULT 0,
+    deferrals INTEGER NOT NULL DEFAULT 0,
+    absent INTEGER NOT NULL DEFAULT 0,
+    inactive INTEGER NOT NULL DEFAULT 0,
+    calling TEXT NOT NULL DEFAULT ''
+);
+CREATE TABLE IF NOT EXISTS commits (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    timestamp TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS archive (
+    commit_id INTEGER NOT NULL REFERENCES commits(id),
+    email TEXT NOT NULL,
+    name TEXT NOT NULL,
+    participation_score INTEGER NOT NULL DEFAULT 0,
+    deferrals INTEGER NOT NULL DEFAULT 0,
+    absent INTEGER NOT NULL DEFAULT 0,
+    inactive INTEGER NOT NULL DEFAULT 0,
+    calling TEXT NOT NULL DEFAULT '',
+    PRIMARY KEY (commit_id, email)
+);
+CREATE TABLE IF NOT EXISTS sessions (
+    id TEXT PRIMARY KEY,
+    committed INTEGER NOT NULL DEFAULT 0,
+    commit_id INTEGER REFERENCES commits(id)
+);
+CREATE TABLE IF NOT EXISTS events (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    timestamp TEXT NOT NULL,
+    session TEXT NOT NULL REFERENCES sessions(id),
+    email TEXT NOT NULL,
+    outcome TEXT NOT NULL,
+    undo INTEGER NOT NULL DEFAULT 0,
+    counter TEXT NOT NULL DEFAULT 'participation_score',
+    points INTEGER NOT NULL DEFAULT 1,
+    weighted INTEGER NOT NULL DEFAULT 1
+);
+CREATE TABLE IF NOT EXISTS attendance (
+    date TEXT NOT NULL,
+    email TEXT NOT NULL,
+    status TEXT NOT NULL,
+    PRIMARY KEY (date, email)
+);
+CREATE TABLE IF NOT EXISTS notes (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    timestamp TEXT NOT NULL,
+    email TEXT NOT NULL,
+    notes TEXT NOT NULL,
+    tags TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS draws (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    timestamp TEXT NOT NULL,
+    session TEXT NOT NULL REFERENCES sessions(id),
+    strategy TEXT NOT NULL,
+    seed TEXT NOT NULL,
+    excluded TEXT NOT NULL,
+    cooldown TEXT NOT NULL,
+    warned TEXT NOT NULL
+);
+CREATE VIEW IF NOT EXISTS pending AS
+    SELECT email,
+        SUM(CASE WHEN counter = 'participation_score' THEN points * (1 - 2 * undo) ELSE 0 END)
+            AS participation_score,
+        SUM(CASE WHEN counter = 'deferrals' THEN points * (1 - 2 * undo) ELSE 0 END)
+            AS deferrals,
+        SUM(CASE WHEN counter = 'absent' THEN points * (1 - 2 * undo) ELSE 0 END) AS absent
+    FROM events JOIN sessions ON events.session = sessions.id
+    WHERE NOT sessions.committed
+    GROUP BY email;
+CREATE VIEW IF NOT EXISTS totals AS
+    SELECT s.email, s.name,
+        s.participation_score + COALESCE(p.participation_score, 0) AS participation_score,
+        s.deferrals + COALESCE(p.deferrals, 0) AS deferrals,
+        s.absent + COALESCE(p.absent, 0) AS absent
+    FROM students s LEFT JOIN pending p ON s.email = p.email;
+";
+
+pub struct SqliteStore {
+    conn: Connection,
+}
+
+impl SqliteStore {
+    pub fn open(path: &OsStr) -> anyhow::Result<Self> {
+        let conn = Connection::open(path)?;
+        conn.execute_batch(SCHEMA)?;
+        Ok(Self { conn })
+    }
+}
+
+impl SqliteStore {
+    // The students in `table`, `students` or `archive`, that match
+    // `filter`.
+    fn students(
+        &self,
+        table: &str,
+        filter: &str,
+        params: impl Params,
+    ) -> anyhow::Result<HashMap<StudentKey, Student>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT email, name, participation_score, deferrals, absent, inactive, calling
+                 FROM {table} WHERE {filter}"
+        ))?;
+        let rows = stmt.query_map(params, |r| {
+            Ok((
+                Student {
+                    participation_score: r.get(2)?,
+                    deferrals: r.get(3)?,
+                    absent: r.get(4)?,
+                    inactive: r.get(5)?,
+                    ..Student::new(r.get(1)?, r.get(0)?)
+                },
+                r.get::<_, String>(6)?,
+            ))
+        })?;
+
+        let mut students = HashMap::new();
+        for row in rows {
+            let (mut s, calling) = row?;
+            s.calling = Calling::from_str(&calling)?;
+            students.insert(s.email.clone(), s);
+        }
+        Ok(students)
+    }
+
+    // The events in the sessions that match `filter`, in order.
+    fn events_in(&self, filter: &str, params: impl Params) -> anyhow::Result<Vec<Event>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT timestamp, session, email, outcome, undo, counter, points, weighted
+             FROM events JOIN sessions ON events.session = sessions.id
+             WHERE {filter}
+             ORDER BY events.id"
+        ))?;
+        let rows = stmt.query_map(params, |r| {
+            Ok((
+                r.get(0)?,
+                r.get(1)?,
+                r.get(2)?,
+                r.get(3)?,
+                r.get(4)?,
+                r.get::<_, String>(5)?,
+                r.get(6)?,
+                r.get(7)?,
+            ))
+        })?;
+
+        let mut events = Vec::new();
+        for row in rows {
+            let (timestamp, session, email, outcome, undo, counter, points, weighted) = row?;
+            events.push(Event {
+                timestamp,
+                session,
+                email,
+                outcome,
+                undo,
+                counter: Counter::from_str(&counter)?,
+                points,
+                weighted,
+            });
+        }
+        Ok(events)
+    }
+
+    // The draws in the sessions that match `filter`, in order.
+    fn draws_in(&self, filter: &str, params: impl Params) -> anyhow::Result<Vec<Draw>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT timestamp, session, strategy, seed, excluded, cooldown, warned
+             FROM draws JOIN sessions ON draws.session = sessions.id
+             WHERE {filter}
+             ORDER BY draws.id"
+        ))?;
+        let rows = stmt.query_map(params, |r| {
+            Ok((
+                r.get(0)?,
+                r.get(1)?,
+                r.get(2)?,
+                r.get::<_, String>(3)?,
+                r.get(4)?,
+                r.get(5)?,
+                r.get(6)?,
+            ))
+        })?;
+
+        let mut draws = Vec::new();
+        for row in rows {
+            let (timestamp, session, strategy, seed, excluded, cooldown, warned) = row?;
+            draws.push(Draw {
+                timestamp,
+                session,
+                strategy,
+                seed: seed.parse()?,
+                excluded,
+                cooldown,
+                warned,
+            });
+        }
+        Ok(draws)
+    }
+}
+
+fn insert_event(conn: &Connection, event: &Event) -> anyhow::Result<()> {
+    conn.execute(
+        "INSERT OR IGNORE INTO sessions (id) VALUES (?1)",
+        params![event.session],
+    )?;
+    conn.execute(
+        "INSERT INTO events
+            (timestamp, session, email, outcome, undo, counter, points, weighted)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![
+            event.timestamp,
+            event.session,
+            event.email,
+            event.outcome,
+            event.undo,
+            event.counter.to_string(),
+            event.points,
+            event.weighted
+        ],
+    )?;
+    Ok(())
+}
+
+// SQLite integers are signed, so the seed is kept as text.
+fn insert_draw(conn: &Connection, draw: &Draw) -> anyhow::Result<()> {
+    conn.execute(
+        "INSERT OR IGNORE INTO sessions (id) VALUES (?1)",
+        params![draw.session],
+    )?;
+    conn.execute(
+        "INSERT INTO draws
+            (timestamp, session, strategy, seed, excluded, cooldown, warned)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            draw.timestamp,
+            draw.session,
+            draw.strategy,
+            draw.seed.to_string(),
+            draw.excluded,
+            draw.cooldown,
+            draw.warned
+        ],
+    )?;
+    Ok(())
+}
+
+// A new commit at `at`, keeping `roster` as the one it replaced.
+// Returns its id.
+fn insert_commit(
+    conn: &Connection,
+    at: DateTime<Local>,
+    roster: &HashMap<StudentKey, Student>,
+) -> anyhow::Result<i64> {
+    conn.execute("INSERT INTO commits (timestamp) VALUES (?1)", params![at])?;
+    let id = conn.last_insert_rowid();
+    for s in roster.values() {
+        conn.execute(
+            "INSERT INTO archive
+                (commit_id, email, name, participation_score, deferrals, absent, inactive,
+                 calling)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                id,
+                s.email,
+                s.name,
+                s.participation_score,
+                s.deferrals,
+                s.absent,
+                s.inactive,
+                s.calling.to_string()
+            ],
+        )?;
+    }
+    Ok(id)
+}
+
+impl Store for SqliteStore {
+    fn load(&self) -> anyhow::Result<HashMap<StudentKey, Student>> {
+        self.students("students", "1", [])
+    }
+
+    fn events(&self) -> anyhow::Result<Vec<Event>> {
+        self.events_in("NOT sessions.committed", [])
+    }
+
+    fn history(&self) -> anyhow::Result<Vec<Event>> {
+        self.events_in("1", [])
+    }
+
+    fn append(&mut self, event: &Event) -> anyhow::Result<()> {
+        let tx = self.conn.transaction()?;
+        insert_event(&tx, event)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    // The `totals` view is always current, so there is nothing to do.
+    fn save(&mut self, _students: &HashMap<StudentKey, Student>) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn write_roster(&mut self, students: &HashMap<StudentKey, Student>) -> anyhow::Result<()> {
+        let tx = self.conn.transaction()?;
+        tx.execute("DELETE FROM students", [])?;
+        for s in students.values() {
+            tx.execute(
+                "INSERT INTO students
+                    (email, name, participation_score, deferrals, absent, inactive, calling)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    s.email,
+                    s.name,
+                    s.participation_score,
+                    s.deferrals,
+                    s.absent,
+                    s.inactive,
+                    s.calling.to_string()
+                ],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn commit(&mut self) -> anyhow::Result<()> {
+        let roster = self.load()?;
+        let tx = self.conn.transaction()?;
+        let id = insert_commit(&tx, Local::now(), &roster)?;
+        tx.execute(
+            "UPDATE students SET
+                participation_score = t.participation_score,
+                deferrals = t.deferrals,
+                absent = t.absent
+             FROM totals t WHERE students.email = t.email",
+            [],
+        )?;
+        tx.execute(
+            "UPDATE sessions SET committed = 1, commit_id = ?1 WHERE NOT committed",
+            params![id],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn attendance(&self) -> anyhow::Result<Vec<attendance::Record>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT date, email, status FROM attendance ORDER BY date")?;
+        let rows = stmt.query_map([], |r| Ok((r.get(0)?, r.get(1)?, r.get::<_, String>(2)?)))?;
+
+        let mut records = Vec::new();
+        for row in rows {
+            let (date, email, status) = row?;
+            records.push(attendance::Record {
+                date,
+                email,
+                status: attendance::Attendance::from_str(&status)?,
+            });
+        }
+        Ok(records)
+    }
+
+    fn record_attendance(&mut self, record: &attendance::Record) -> anyhow::Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO attendance (date, email, status) VALUES (?1, ?2, ?3)",
+            params![record.date, record.email, record.status.to_string()],
+        )?;
+        Ok(())
+    }
+
+    fn notes(&self) -> anyhow::Result<Vec<notes::Record>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT timestamp, email, notes, tags FROM notes ORDER BY id")?;
+        let rows = stmt.query_map([], |r| {
+            Ok(notes::Record {
+                timestamp: r.get(0)?,
+                email: r.get(1)?,
+                notes: r.get(2)?,
+                tags: r.get(3)?,
+            })
+        })?;
+
+        let mut records = Vec::new();
+        for r in rows {
+            records.push(r?);
+        }
+        Ok(records)
+    }
+
+    fn record_notes(&mut self, record: &notes::Record) -> anyhow::Result<()> {
+        self.conn.execute(
+            "INSERT INTO notes (timestamp, email, notes, tags) VALUES (?1, ?2, ?3, ?4)",
+            params![record.timestamp, record.email, record.notes, record.tags],
+        )?;
+        Ok(())
+    }
+
+    fn draws(&self) -> anyhow::Result<Vec<Draw>> {
+        self.draws_in("NOT sessions.committed", [])
+    }
+
+    fn record_draw(&mut self, draw: &Draw) -> anyhow::Result<()> {
+        let tx = self.conn.transaction()?;
+        insert_draw(&tx, draw)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn archives(&self) -> anyhow::Result<Vec<Archive>> {
+        let mut archives = Vec::new();
+        let mut stmt = self.conn.prepare("SELECT id FROM commits ORDER BY id")?;
+        let ids = stmt.query_map([], |r| r.get::<_, i64>(0))?;
+        for id in ids {
+            let id = id?;
+            archives.push(Archive {
+                roster: self.students("archive", "commit_id = ?1", [id])?,
+                events: self.events_in("sessions.commit_id = ?1", [id])?,
+                draws: self.draws_in("sessions.commit_id = ?1", [id])?,
+            });
+        }
+        Ok(archives)
+    }
+
+    fn archive(&mut self, archive: &Archive) -> anyhow::Result<()> {
+        let latest = archive
+            .events
+            .iter()
+            .map(|e| e.timestamp)
+            .chain(archive.draws.iter().map(|d| d.timestamp))
+            .max();
+        let tx = self.conn.transaction()?;
+        let id = insert_commit(&tx, latest.unwrap_or_else(Local::now), &archive.roster)?;
+        for e in &archive.events {
+            insert_event(&tx, e)?;
+        }
+        for d in &archive.draws {
+            insert_draw(&tx, d)?;
+        }
+        let sessions = archive
+            .events
+            .iter()
+            .map(|e| &e.session)
+            .chain(archive.draws.iter().map(|d| &d.session));
+        for session in sessions {
+            tx.execute(
+                "UPDATE sessions SET committed = 1, commit_id = ?1 WHERE id = ?2",
+                params![id, session],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,144 @@
+// The roster model: a student, with the counters the roster keeps for
+// them, and the state that only lives as long as the session.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    attendance::Attendance,
+    calling::Calling,
+    events::{self, Counter},
+};
+
+/// Students are known by their email.
+pub type StudentKey = String;
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Student {
+    pub name: String,
+    pub email: StudentKey,
+    pub participation_score: usize,
+    pub deferrals: usize,
+    pub absent: usize,
+    /// Dropped from the course, but kept for their history
+    #[serde(default)]
+    pub inactive: bool,
+    /// Any accommodation in how they are called on
+    #[serde(default)]
+    pub calling: Calling,
+    #[serde(skip_serializing, default)]
+    pub answered_today: usize,
+    #[serde(skip_serializing, default)]
+    pub color: usize, // the offset into COLORS
+    #[serde(skip_serializing, default)]
+    pub attendance: Option<Attendance>, // today's, if taken
+    #[serde(skip_serializing, default)]
+    pub history: Vec<events::Event>, // every event on record, oldest first
+    #[serde(skip_serializing, default)]
+    pub notes: String, // the instructor's, kept apart from the roster
+    #[serde(skip_serializing, default)]
+    pub tags: Vec<String>,
+}
+
+impl Student {
+    /// A student new to the roster, with nothing on record.
+    pub fn new(name: String, email: StudentKey) -> Self {
+        Student {
+            name,
+            email,
+            participation_score: 0,
+            deferrals: 0,
+            absent: 0,
+            inactive: false,
+            calling: Calling::Normal,
+            answered_today: 0,
+            color: 0,
+            attendance: None,
+            history: Vec::new(),
+            notes: String::new(),
+            tags: Vec::new(),
+        }
+    }
+
+    /// Without a roll call, everyone still enrolled is assumed to be
+    /// here.
+    pub fn in_room(&self) -> bool {
+        !self.inactive && self.attendance.is_none_or(|a| a.in_room())
+    }
+
+    /// What the biased strategies and the colors go by: participation
+    /// less deferrals, but for whatever outcomes in their history
+    /// aren't weighted.
+    pub fn score(&self) -> isize {
+        let unweighted: isize = self
+            .history
+            .iter()
+            .filter(|e| !e.weighted)
+            .map(events::Event::counted)
+            .sum();
+        self.participation_score as isize - self.deferrals as isize - unweighted
+    }
+
+    /// When they were last called on, by their whole history. An undo
+    /// doesn't take back that they were called on.
+    pub fn last_called(&self) -> Option<chrono::DateTime<chrono::Local>> {
+        self.history
+            .iter()
+            .filter(|e| !e.undo)
+            .map(|e| e.timestamp)
+            .max()
+    }
+
+    /// Fold a logged event into the counters, or take it back out
+    /// again for an undo. Only events from the current session earn
+    /// flames, for adding to the participation score.
+    pub fn apply(&mut self, event: &events::Event, session: &str) {
+        let add = |c: &mut usize, n: usize| {
+            *c = if event.undo {
+                c.saturating_sub(n)
+            } else {
+                *c + n
+            }
+        };
+        match event.counter {
+            Counter::ParticipationScore => {
+                add(&mut self.participation_score, event.points);
+                if event.session == session {
+                    add(&mut self.answered_today, 1);
+                }
+            }
+            Counter::Deferrals => add(&mut self.deferrals, event.points),
+            Counter::Absent => add(&mut self.absent, event.points),
+            Counter::None => {}
+        }
+    }
+}
+
+pub const COLORS: &str = "🔴🟠🟡🟢🔵";
+pub const NUM_COLORS: usize = 5;
+
+impl fmt::Display for Student {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let flames = "🔥".to_string().repeat(self.answered_today);
+        let cs: Vec<_> = COLORS.chars().collect();
+        write!(
+            f,
+            "{}{:3} {} {} {}",
+            cs[self.color], self.participation_score, flames, self.name, flames
+        )?;
+        match self.calling {
+            Calling::Normal => {}
+            Calling::Exempt => write!(f, " 🚫")?,
+            Calling::Volunteer => write!(f, " 🙋")?,
+            Calling::Warn => write!(f, " 🔔")?,
+        }
+        if !self.notes.is_empty() {
+            write!(f, " 📝")?;
+        }
+        for t in &self.tags {
+            write!(f, " #{t}")?;
+        }
+        Ok(())
+    }
+}
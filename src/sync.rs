@@ -0,0 +1,109 @@
+// Keeping the roster in step with the registrar's enrollment as students
+// add and drop the course. The enrollment list is read like an import,
+// and compared with the roster by email: newcomers are added, names
+// brought up to date, and anyone no longer enrolled is marked inactive
+// rather than removed, so their history stays and they come back with
+// it if they re-enroll.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
+
+use crate::{Student, StudentKey};
+
+/// One way the roster differs from the enrollment.
+#[derive(Debug)]
+pub enum Change {
+    /// Newly enrolled
+    Add(Student),
+    /// Enrolled again after being dropped
+    Return { email: StudentKey, name: String },
+    /// Enrolled under another name
+    Rename {
+        email: StudentKey,
+        from: String,
+        to: String,
+    },
+    /// No longer enrolled
+    Drop { email: StudentKey, name: String },
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Change::Add(s) => write!(f, "add {} <{}>", s.name, s.email),
+            Change::Return { email, name } => write!(f, "re-enroll {name} <{email}>"),
+            Change::Rename { email, from, to } => write!(f, "rename {from} to {to} <{email}>"),
+            Change::Drop { email, name } => write!(f, "drop {name} <{email}>"),
+        }
+    }
+}
+
+/// How `roster` differs from `enrolled`: additions, then returns,
+/// renames, and drops, each by name.
+pub fn diff(roster: &HashMap<StudentKey, Student>, enrolled: Vec<Student>) -> Vec<Change> {
+    let mut changes = Vec::new();
+    let mut enrolled_keys = HashSet::new();
+    for s in enrolled {
+        enrolled_keys.insert(s.email.clone());
+        match roster.get(&s.email) {
+            None => changes.push(Change::Add(s)),
+            Some(r) => {
+                if r.inactive {
+                    changes.push(Change::Return {
+                        email: s.email.clone(),
+                        name: r.name.clone(),
+                    });
+                }
+                if r.name != s.name {
+                    changes.push(Change::Rename {
+                        email: s.email,
+                        from: r.name.clone(),
+                        to: s.name,
+                    });
+                }
+            }
+        }
+    }
+    for r in roster.values() {
+        if !r.inactive && !enrolled_keys.contains(&r.email) {
+            changes.push(Change::Drop {
+                email: r.email.clone(),
+                name: r.name.clone(),
+            });
+        }
+    }
+
+    changes.sort_by_key(|c| match c {
+        Change::Add(s) => (0, s.name.clone()),
+        Change::Return { name, .. } => (1, name.clone()),
+        Change::Rename { from, .. } => (2, from.clone()),
+        Change::Drop { name, .. } => (3, name.clone()),
+    });
+    changes
+}
+
+/// Make `change` to `roster`.
+pub fn apply(roster: &mut HashMap<StudentKey, Student>, change: Change) {
+    match change {
+        Change::Add(s) => {
+            roster.insert(s.email.clone(), s);
+        }
+        Change::Return { email, .. } => {
+            if let Some(s) = roster.get_mut(&email) {
+                s.inactive = false;
+            }
+        }
+        Change::Rename { email, to, .. } => {
+            if let Some(s) = roster.get_mut(&email) {
+                s.name = to;
+            }
+        }
+        Change::Drop { email, .. } => {
+            if let Some(s) = roster.get_mut(&email) {
+                s.inactive = true;
+            }
+        }
+    }
+}
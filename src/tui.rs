@@ -0,0 +1,739 @@
+// The terminal frontend: drawing the `App`, and turning key presses
+// into what they do. Input comes from an `EventSource`, which is the
+// terminal when running for real, and a script in the tests.
+//
+// Mostly an example taken from https://github.com/ratatui-org/ratatui/blob/main/examples/user_input.rs
+
+use std::{collections::VecDeque, io};
+
+use ratatui::{
+    backend::Backend,
+    buffer::Buffer,
+    crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+    layout::{Constraint, Layout, Position},
+    prelude::{Alignment, Rect},
+    style::{Color, Modifier, Style, Stylize},
+    text::{Line, Span, Text},
+    widgets::{Block, Clear, List, ListItem, Padding, Paragraph, Sparkline, Tabs, Widget, Wrap},
+    Frame, Terminal,
+};
+
+use crate::{
+    app::{App, Detail, DisplayMode, Field, InputMode, Status},
+    attendance::Attendance,
+    calling::Calling,
+};
+
+/// Where `run_app` gets its input from.
+pub trait EventSource {
+    /// The next event, or `None` when there are no more.
+    fn next(&mut self) -> io::Result<Option<Event>>;
+}
+
+/// Events from the terminal, which never run out.
+pub struct Crossterm;
+
+impl EventSource for Crossterm {
+    fn next(&mut self) -> io::Result<Option<Event>> {
+        event::read().map(Some)
+    }
+}
+
+/// A script of events, played back in order.
+impl EventSource for VecDeque<Event> {
+    fn next(&mut self) -> io::Result<Option<Event>> {
+        Ok(self.pop_front())
+    }
+}
+
+/// The styles the UI is drawn with.
+#[derive(Debug, Clone, Copy)]
+pub struct Theme {
+    /// The selected student
+    selected: Style,
+    /// The query while searching
+    searching: Style,
+    /// The student, roll call, and quit popups
+    popup: Style,
+    /// Errors on the status line
+    error: Style,
+}
+
+impl Theme {
+    pub const NAMES: [&'static str; 3] = ["dark", "light", "mono"];
+
+    pub fn by_name(name: &str) -> anyhow::Result<Self> {
+        let popup = Style::default().add_modifier(Modifier::BOLD);
+        match name {
+            "dark" => Ok(Theme {
+                selected: Style::default().bg(Color::Green).fg(Color::Black),
+                searching: Style::default().fg(Color::Green),
+                popup: popup.bg(Color::Gray).fg(Color::Black),
+                error: Style::default().bold().fg(Color::Red),
+            }),
+            "light" => Ok(Theme {
+                selected: Style::default().bg(Color::Blue).fg(Color::White),
+                searching: Style::default().fg(Color::Blue),
+                popup: popup.bg(Color::DarkGray).fg(Color::White),
+                error: Style::default().bold().fg(Color::Red),
+            }),
+            // For terminals without color, or people who'd rather not.
+            "mono" => Ok(Theme {
+                selected: Style::default().reversed(),
+                searching: Style::default().underlined(),
+                popup: popup.reversed(),
+                error: Style::default().bold().underlined(),
+            }),
+            _ => anyhow::bail!(
+                "Unknown theme \"{name}\", expected one of {}.",
+                Theme::NAMES.join(", ")
+            ),
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::by_name("dark").expect("the dark theme exists")
+    }
+}
+
+/// Draw the app and handle events until the user quits, or the events
+/// run out.
+pub fn run_app<B: Backend>(
+    terminal: &mut Terminal<B>,
+    app: &mut App,
+    events: &mut dyn EventSource,
+    theme: &Theme,
+) -> io::Result<()> {
+    loop {
+        terminal.draw(|f| ui(f, app, theme))?;
+
+        let Some(event) = events.next()? else {
+            return Ok(());
+        };
+        if let Event::Key(key) = event {
+            match app.input_mode() {
+                InputMode::Command => match key.code {
+                    KeyCode::Char('s') | KeyCode::Char('/') => {
+                        app.display_mode = DisplayMode::Searching;
+                    }
+                    KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        app.redo();
+                    }
+                    KeyCode::Char('r') => {
+                        app.randomize();
+                    }
+                    KeyCode::Char('u') => {
+                        app.undo();
+                    }
+                    KeyCode::Char('t') => {
+                        app.start_roll_call();
+                    }
+                    KeyCode::Char('m') => {
+                        app.next_strategy();
+                    }
+                    KeyCode::Tab => {
+                        app.next_section();
+                    }
+                    KeyCode::BackTab => {
+                        app.previous_section();
+                    }
+                    KeyCode::Char('q') if app.dirty() => {
+                        app.quitting = true;
+                    }
+                    KeyCode::Char('q') => {
+                        return Ok(());
+                    }
+                    KeyCode::Down => {
+                        app.move_selection_down();
+                    }
+                    KeyCode::Up => {
+                        app.move_selection_up();
+                    }
+                    KeyCode::Char('p') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        app.move_selection_up();
+                    }
+                    KeyCode::Char('n') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        app.move_selection_down();
+                    }
+                    KeyCode::Enter => {
+                        app.display_selected_student();
+                    }
+                    KeyCode::Char('i') => {
+                        app.display_detail();
+                    }
+                    _ => {}
+                },
+                InputMode::Searching if key.kind == KeyEventKind::Press => match key.code {
+                    KeyCode::Enter => {
+                        app.display_selected_student();
+                    }
+                    KeyCode::Char('o') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        app.display_detail();
+                    }
+                    KeyCode::Char('p') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        app.move_selection_up();
+                    }
+                    KeyCode::Char('n') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        app.move_selection_down();
+                    }
+                    KeyCode::Backspace => {
+                        app.delete_char();
+                    }
+                    KeyCode::Left => {
+                        app.move_cursor_left();
+                    }
+                    KeyCode::Right => {
+                        app.move_cursor_right();
+                    }
+                    KeyCode::Down => {
+                        app.move_selection_down();
+                    }
+                    KeyCode::Up => {
+                        app.move_selection_up();
+                    }
+                    KeyCode::Char('g') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        app.display_mode = DisplayMode::Command;
+                        app.input_clear();
+                    }
+                    KeyCode::Esc => {
+                        app.display_mode = DisplayMode::Command;
+                        app.input_clear();
+                    }
+                    KeyCode::Char(to_insert) => {
+                        app.enter_char(to_insert);
+                    }
+                    _ => {}
+                },
+                InputMode::Searching => {}
+                InputMode::RollCall => match key.code {
+                    KeyCode::Char('p') => {
+                        app.mark_attendance(Attendance::Present);
+                    }
+                    KeyCode::Char('a') => {
+                        app.mark_attendance(Attendance::Absent);
+                    }
+                    KeyCode::Char('l') => {
+                        app.mark_attendance(Attendance::Late);
+                    }
+                    KeyCode::Char('e') => {
+                        app.mark_attendance(Attendance::Excused);
+                    }
+                    KeyCode::Up | KeyCode::Backspace => {
+                        app.roll_call_back();
+                    }
+                    KeyCode::Char('g') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        app.roll_call_escape();
+                    }
+                    KeyCode::Esc => {
+                        app.roll_call_escape();
+                    }
+                    _ => {}
+                },
+                InputMode::Quitting => match key.code {
+                    KeyCode::Char('y') => {
+                        if app.commit() {
+                            return Ok(());
+                        }
+                        app.quitting = false;
+                    }
+                    KeyCode::Char('n') => {
+                        return Ok(());
+                    }
+                    KeyCode::Char('g') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        app.quitting = false;
+                    }
+                    KeyCode::Esc => {
+                        app.quitting = false;
+                    }
+                    _ => {}
+                },
+                // Nothing here records anything.
+                InputMode::Detail => match key.code {
+                    KeyCode::Down | KeyCode::Char('j') => {
+                        app.detail_scroll_down();
+                    }
+                    KeyCode::Up | KeyCode::Char('k') => {
+                        app.detail_scroll_up();
+                    }
+                    KeyCode::Char('g') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        app.detail_escape();
+                    }
+                    KeyCode::Esc | KeyCode::Char('q') => {
+                        app.detail_escape();
+                    }
+                    KeyCode::Char('n') => {
+                        app.edit(Field::Notes);
+                    }
+                    KeyCode::Char('t') => {
+                        app.edit(Field::Tags);
+                    }
+                    _ => {}
+                },
+                InputMode::Editing if key.kind == KeyEventKind::Press => {
+                    let Some(e) = app.editing.as_mut() else {
+                        continue;
+                    };
+                    match key.code {
+                        KeyCode::Enter => {
+                            app.edit_save();
+                        }
+                        KeyCode::Char('g') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                            app.edit_escape();
+                        }
+                        KeyCode::Esc => {
+                            app.edit_escape();
+                        }
+                        KeyCode::Backspace => {
+                            e.delete_char();
+                        }
+                        KeyCode::Left => {
+                            e.move_cursor_left();
+                        }
+                        KeyCode::Right => {
+                            e.move_cursor_right();
+                        }
+                        KeyCode::Char(to_insert) => {
+                            e.enter_char(to_insert);
+                        }
+                        _ => {}
+                    }
+                }
+                InputMode::Editing => {}
+                InputMode::Student => match key.code {
+                    KeyCode::Char('g') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        app.student_escape();
+                    }
+                    KeyCode::Esc => {
+                        app.student_escape();
+                    }
+                    // Whatever the course's outcomes are recorded with
+                    KeyCode::Char(c) => {
+                        if let Some(o) = app.outcomes.iter().find(|o| o.key == c).cloned() {
+                            app.student_record(&o);
+                        }
+                    }
+                    _ => {}
+                },
+            }
+        }
+    }
+}
+
+// How many rows `paragraph`, of `text`, takes at `width`. Rather than
+// second-guess how ratatui wraps, it is rendered into a scratch buffer
+// as tall as it could possibly be, and the rows are counted down to
+// the last one written.
+fn wrapped_height(paragraph: &Paragraph, text: &Text, width: u16) -> u16 {
+    // Each row takes at least a character, or an empty line. A buffer
+    // holds at most `u16::MAX` cells, far more than a screen's worth.
+    let width = width.max(1);
+    let tallest: usize = text.lines.iter().map(|l| l.width().max(1)).sum();
+    let height = tallest.min(usize::from(u16::MAX / width)) as u16;
+    let area = Rect::new(0, 0, width, height);
+    let mut scratch = Buffer::empty(area);
+    paragraph.render(area, &mut scratch);
+    let rows = scratch
+        .content()
+        .chunks(usize::from(area.width))
+        .rposition(|row| row.iter().any(|c| c.symbol() != " "))
+        .map_or(0, |last| last + 1);
+    rows as u16
+}
+
+fn ui(f: &mut Frame, app: &App, theme: &Theme) {
+    // The tabs only take up room when there is more than one section.
+    let tabs = if app.sections.len() > 1 { 1 } else { 0 };
+    let (msg, style) = match app.input_mode() {
+        InputMode::Command => {
+            let mut msg = vec![
+                "q".bold(),
+                " = quit, ".into(),
+                "r".bold(),
+                " = 🎲 randomize, ".into(),
+                "m".bold(),
+                " = selection mode, ".into(),
+                "s".bold(),
+                " = search, ".into(),
+                "t".bold(),
+                " = take attendance, ".into(),
+                "u".bold(),
+                " = undo, ".into(),
+                "C-r".bold(),
+                " = redo, ".into(),
+                "i".bold(),
+                " = details, ".into(),
+                "↑".bold(),
+                " and ".into(),
+                "↓".bold(),
+                " = navigate students.".into(),
+            ];
+            if tabs > 0 {
+                msg.splice(2..2, ["Tab".bold(), " = next section, ".into()]);
+            }
+            (msg, Style::default())
+        }
+        InputMode::Searching => (
+            vec![
+                "Esc".bold(),
+                " = go back, ".into(),
+                "Enter".bold(),
+                " = select a student, ".into(),
+                "C-o".bold(),
+                " = details, ".into(),
+                "↑".bold(),
+                " and ".into(),
+                "↓".bold(),
+                " = navigate students.".into(),
+            ],
+            Style::default(),
+        ),
+        InputMode::Student => {
+            let mut msg = vec!["Esc".bold(), " to go back".into()];
+            for o in &app.outcomes {
+                msg.extend([", ".into(), o.key.to_string().bold(), " = ".into()]);
+                msg.push(o.name.clone().into());
+            }
+            msg.push(".".into());
+            (msg, Style::default())
+        }
+        InputMode::Detail => (
+            vec![
+                "Esc".bold(),
+                " to go back, ".into(),
+                "n".bold(),
+                " = edit notes, ".into(),
+                "t".bold(),
+                " = edit tags, ".into(),
+                "↑".bold(),
+                " and ".into(),
+                "↓".bold(),
+                " = scroll the history.".into(),
+            ],
+            Style::default(),
+        ),
+        InputMode::Editing => (
+            vec![
+                "Esc".bold(),
+                " = cancel, ".into(),
+                "Enter".bold(),
+                " = save.".into(),
+            ],
+            Style::default(),
+        ),
+        InputMode::RollCall => (
+            vec![
+                "Esc".bold(),
+                " = done, ".into(),
+                "p".bold(),
+                " = present, ".into(),
+                "a".bold(),
+                " = absent, ".into(),
+                "l".bold(),
+                " = late, ".into(),
+                "e".bold(),
+                " = excused, ".into(),
+                "↑".bold(),
+                " = back.".into(),
+            ],
+            Style::default(),
+        ),
+        InputMode::Quitting => (
+            vec![
+                "Esc".bold(),
+                " to go back, ".into(),
+                "y".bold(),
+                " = commit and quit, ".into(),
+                "n".bold(),
+                " = quit without committing.".into(),
+            ],
+            Style::default(),
+        ),
+    };
+    // The help wraps, e.g. when a course has many outcomes.
+    let area = f.area();
+    let text = Text::from(Line::from(msg)).patch_style(style);
+    let help_message = Paragraph::new(text.clone()).wrap(Wrap { trim: true });
+    let help = wrapped_height(&help_message, &text, area.width);
+    let vertical = Layout::vertical([
+        Constraint::Length(tabs),
+        Constraint::Length(help),
+        Constraint::Length(3),
+        Constraint::Min(1),
+        Constraint::Length(1),
+    ]);
+    let [tabs_area, help_area, input_area, students_area, status_area] = vertical.areas(area);
+
+    if tabs > 0 {
+        let titles = app.sections.iter().map(|s| {
+            if s.dirty {
+                format!("{}*", s.name)
+            } else {
+                s.name.clone()
+            }
+        });
+        let tabs = Tabs::new(titles)
+            .select(app.current)
+            .highlight_style(theme.selected);
+        f.render_widget(tabs, tabs_area);
+    }
+
+    f.render_widget(help_message, help_area);
+
+    let input = Paragraph::new(app.input.as_str())
+        .style(match app.display_mode {
+            DisplayMode::Searching => theme.searching,
+            _ => Style::default(),
+        })
+        .block(Block::bordered().title("Query"));
+    f.render_widget(input, input_area);
+    match app.display_mode {
+        // Hide the cursor. `Frame` does this by default, so we don't need to do anything here
+        DisplayMode::Command => {}
+
+        // Make the cursor visible and ask ratatui to put it at the specified coordinates after
+        // rendering
+        #[allow(clippy::cast_possible_truncation)]
+        DisplayMode::Searching => f.set_cursor_position(Position::new(
+            // Draw the cursor at the current position in the input field.
+            // This position is can be controlled via the left and right arrow key
+            input_area.x + app.character_index as u16 + 1,
+            // Move one line down, from the border to the input line
+            input_area.y + 1,
+        )),
+    };
+    let students: Vec<ListItem> = app
+        .students_view()
+        .iter()
+        .enumerate()
+        .map(|(i, key)| {
+            let s = app
+                .students
+                .get(key)
+                .expect("View has inconsistent name with the student db.");
+            let mut content = if app.selection.is_some() && i == app.selection.unwrap() {
+                Line::from(Span::styled(format!("{s}"), theme.selected))
+            } else {
+                Line::from(Span::raw(format!("{}", s)))
+            };
+            // Someone who wants a warning gets it while they're second.
+            if app.heads_up.as_ref() == Some(key) {
+                content.push_span(" heads up, you're next".bold());
+            }
+            // Still listed, so they can be picked by hand.
+            let content = if app.cooling.contains(key) {
+                content.patch_style(Modifier::DIM)
+            } else {
+                content
+            };
+            ListItem::new(content)
+        })
+        .collect();
+    let students = List::new(students).block(
+        Block::bordered()
+            .title(format!("Students (🎲 {})", app.strategy.name()))
+            .padding(Padding::new(2, 2, 1, 1)),
+    );
+    f.render_widget(students, students_area);
+
+    if let Some(status) = &app.status {
+        let line = match status {
+            Status::Info(msg) => msg.as_str().italic(),
+            Status::Error(msg) => Span::styled(msg.as_str(), theme.error),
+        };
+        f.render_widget(Paragraph::new(line), status_area);
+    }
+
+    if let Some(s) = &app.student_display {
+        let area = centered_rect(60, 20, area);
+        let block = Paragraph::new(format!("🎉{s}🎉"))
+            .style(Style::default())
+            .alignment(Alignment::Center)
+            .block(Block::bordered().title("Student ✋").padding(Padding::new(
+                0,
+                0,
+                area.height / 2 - 1,
+                0,
+            )))
+            .style(theme.popup);
+
+        f.render_widget(Clear, area); //this clears out the background
+        f.render_widget(block, area);
+    }
+
+    if let Some(d) = &app.detail {
+        detail(f, app, d, theme, centered_rect(80, 80, area));
+    }
+
+    if let Some(e) = &app.editing {
+        let area = centered_rect(60, 20, area);
+        let what = match e.field {
+            Field::Notes => "Notes",
+            Field::Tags => "Tags, separated by spaces,",
+        };
+        let block = Paragraph::new(e.text.as_str())
+            .block(
+                Block::bordered()
+                    .title(format!("{what} for {}", app.students[&e.email].name))
+                    .padding(Padding::uniform(1)),
+            )
+            .style(theme.popup);
+        f.render_widget(Clear, area);
+        f.render_widget(block, area);
+        #[allow(clippy::cast_possible_truncation)]
+        f.set_cursor_position(Position::new(
+            area.x + 2 + e.text.chars().take(e.cursor).count() as u16,
+            area.y + 2,
+        ));
+    }
+
+    if let (Some(i), Some(s)) = (app.roll_call, app.roll_call_student()) {
+        let area = centered_rect(60, 20, area);
+        let marked = s
+            .attendance
+            .map(|a| format!(" (marked {a})"))
+            .unwrap_or_default();
+        let block = Paragraph::new(format!("{}{marked}", s.name))
+            .alignment(Alignment::Center)
+            .block(
+                Block::bordered()
+                    .title(format!("Attendance 📋 {}/{}", i + 1, app.roll.len()))
+                    .padding(Padding::new(0, 0, area.height / 2 - 1, 0)),
+            )
+            .style(theme.popup);
+
+        f.render_widget(Clear, area);
+        f.render_widget(block, area);
+    }
+
+    if app.quitting {
+        let area = centered_rect(60, 20, area);
+        let question = if app.sections.len() > 1 {
+            "Commit this session's output as the new rosters?"
+        } else {
+            "Commit this session's output as the new roster?"
+        };
+        let block = Paragraph::new(question)
+            .alignment(Alignment::Center)
+            .block(Block::bordered().title("Quit 👋").padding(Padding::new(
+                0,
+                0,
+                area.height / 2 - 1,
+                0,
+            )))
+            .style(theme.popup);
+
+        f.render_widget(Clear, area);
+        f.render_widget(block, area);
+    }
+}
+
+// Everything about a student: their counters and attendance, how they
+// have done session by session, and every outcome on record.
+fn detail(f: &mut Frame, app: &App, d: &Detail, theme: &Theme, area: Rect) {
+    let s = &app.students[&d.email];
+    let block = Block::bordered()
+        .title(format!("Student 📇 {}", s.name))
+        .padding(Padding::horizontal(1))
+        .style(theme.popup);
+    let inner = block.inner(area);
+    f.render_widget(Clear, area);
+    f.render_widget(block, area);
+
+    let count = |a| d.attendance.iter().filter(|r| r.status == a).count();
+    let mut lines = vec![
+        Line::from(format!(
+            "{} <{}>{}",
+            s.name,
+            s.email,
+            if s.inactive { " (dropped)" } else { "" }
+        )),
+        Line::from(format!(
+            "answers {}, deferrals {}, absent {}, score {}, today {}",
+            s.participation_score,
+            s.deferrals,
+            s.absent,
+            s.score(),
+            s.answered_today
+        )),
+        Line::from(format!(
+            "attendance: present {}, absent {}, late {}, excused {}{}",
+            count(Attendance::Present),
+            count(Attendance::Absent),
+            count(Attendance::Late),
+            count(Attendance::Excused),
+            s.attendance
+                .map(|a| format!(" (today {a})"))
+                .unwrap_or_default()
+        )),
+    ];
+    if let Some(t) = s.last_called() {
+        lines.push(Line::from(format!(
+            "last called {}",
+            t.format("%Y-%m-%d %H:%M")
+        )));
+    }
+    match s.calling {
+        Calling::Normal => {}
+        Calling::Exempt => lines.push(Line::from("exempt from cold-calling")),
+        Calling::Volunteer => lines.push(Line::from("only called on when volunteering")),
+        Calling::Warn => lines.push(Line::from("wants a heads up before being called on")),
+    }
+    if !s.tags.is_empty() {
+        let tags: Vec<String> = s.tags.iter().map(|t| format!("#{t}")).collect();
+        lines.push(Line::from(format!("tags: {}", tags.join(" "))));
+    }
+    if !s.notes.is_empty() {
+        lines.push(Line::from(format!("notes: {}", s.notes)));
+    }
+
+    // Notes can run long, and wrap.
+    let wrapped = s.notes.chars().count() / inner.width.max(1) as usize;
+    let [info_area, sparkline_area, history_area] = Layout::vertical([
+        Constraint::Length((lines.len() + wrapped) as u16 + 1),
+        Constraint::Length(4),
+        Constraint::Min(1),
+    ])
+    .areas(inner);
+    f.render_widget(Paragraph::new(lines).wrap(Wrap { trim: false }), info_area);
+
+    let sparkline = Sparkline::default()
+        .block(Block::new().title("Points per session"))
+        .data(&d.points);
+    f.render_widget(sparkline, sparkline_area);
+
+    let history: Vec<Line> = s
+        .history
+        .iter()
+        .rev()
+        .map(|e| {
+            let what = if e.undo {
+                format!("undid {}", e.outcome)
+            } else {
+                e.outcome.clone()
+            };
+            Line::from(format!("{}  {what}", e.timestamp.format("%Y-%m-%d %H:%M")))
+        })
+        .collect();
+    let history = Paragraph::new(history)
+        .block(Block::new().title("History"))
+        .scroll((d.scroll, 0));
+    f.render_widget(history, history_area);
+}
+
+fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
+    let popup_layout = Layout::vertical([
+        Constraint::Percentage((100 - percent_y) / 2),
+        Constraint::Percentage(percent_y),
+        Constraint::Percentage((100 - percent_y) / 2),
+    ])
+    .split(r);
+
+    Layout::horizontal([
+        Constraint::Percentage((100 - percent_x) / 2),
+        Constraint::Percentage(percent_x),
+        Constraint::Percentage((100 - percent_x) / 2),
+    ])
+    .split(popup_layout[1])[1]
+}
@@ -0,0 +1,80 @@
+// Accommodations in how students are called on, as the order shows
+// them.
+
+use participation::{
+    app::{Options, Section},
+    calling::Calling,
+    events::default_outcomes,
+    store,
+};
+use tempfile::TempDir;
+
+mod common;
+
+const ROSTER: &str = "# participation roster v3
+name\temail\tparticipation_score\tdeferrals\tabsent\tinactive\tcalling
+Ada Lovelace\tada@x.edu\t0\t0\t0\tfalse\twarn
+Alan Turing\talan@x.edu\t0\t0\t0\tfalse\t
+Grace Hopper\tgrace@x.edu\t0\t0\t0\tfalse\texempt
+Edsger Dijkstra\tedsger@x.edu\t0\t0\t0\tfalse\tvolunteer
+";
+
+fn class() -> (TempDir, Section) {
+    let (dir, roster) = common::class(Some(ROSTER));
+    let store = store::open(roster.as_os_str(), None).unwrap();
+    let options = Options {
+        strategy: "uniform".to_string(),
+        seed: Some(1),
+        ..Options::default()
+    };
+    let section = Section::new(String::new(), store, &options).unwrap();
+    (dir, section)
+}
+
+#[test]
+fn the_calling_is_read_from_the_roster() {
+    let (_dir, section) = class();
+    assert_eq!(section.students["ada@x.edu"].calling, Calling::Warn);
+    assert_eq!(section.students["alan@x.edu"].calling, Calling::Normal);
+    assert_eq!(section.students["grace@x.edu"].calling, Calling::Exempt);
+    assert_eq!(section.students["edsger@x.edu"].calling, Calling::Volunteer);
+}
+
+#[test]
+fn exempt_students_are_never_at_the_top() {
+    let (_dir, mut section) = class();
+    for _ in 0..20 {
+        section.randomize();
+        let view = section.students_view();
+        assert!(view[..2]
+            .iter()
+            .all(|k| k == "ada@x.edu" || k == "alan@x.edu"));
+    }
+}
+
+#[test]
+fn warn_first_students_get_a_heads_up_before_coming_up() {
+    let (_dir, mut section) = class();
+    // Until Ada is drawn first, and so warned instead.
+    while section.heads_up.is_none() {
+        section.randomize();
+    }
+    assert_eq!(section.heads_up.as_deref(), Some("ada@x.edu"));
+    assert_eq!(section.students_view()[..2], ["alan@x.edu", "ada@x.edu"]);
+
+    // Once Alan is called on, Ada is up, whatever the draw.
+    section.display_selected_student();
+    section.student_record(&default_outcomes()[0]);
+    assert_eq!(section.heads_up, None);
+    assert_eq!(section.students_view()[0], "ada@x.edu");
+    section.randomize();
+    assert_eq!(section.students_view()[0], "ada@x.edu");
+
+    // Having been called on, she is warned again next time.
+    section.display_selected_student();
+    section.student_record(&default_outcomes()[0]);
+    while section.heads_up.is_none() {
+        section.randomize();
+    }
+    assert_eq!(section.heads_up.as_deref(), Some("ada@x.edu"));
+}
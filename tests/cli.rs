@@ -0,0 +1,151 @@
+// The non-interactive subcommands, checked by what they leave in the
+// store.
+
+use participation::{
+    app::{Options, Section},
+    calling::Calling,
+    cli,
+    events::{default_outcomes, Counter, Outcome},
+    store,
+};
+
+mod common;
+use common::ROSTER;
+
+#[test]
+fn record_is_logged_and_committed() {
+    let (_dir, roster) = common::class(Some(ROSTER));
+    let mut store = store::open(roster.as_os_str(), None).unwrap();
+    cli::record(store.as_mut(), "alan@x.edu", "answer", &default_outcomes()).unwrap();
+    cli::record(store.as_mut(), "alan@x.edu", "defer", &default_outcomes()).unwrap();
+    assert_eq!(store.events().unwrap().len(), 2);
+
+    store.commit().unwrap();
+    let students = store.load().unwrap();
+    assert_eq!(students["alan@x.edu"].participation_score, 1);
+    assert_eq!(students["alan@x.edu"].deferrals, 1);
+    assert_eq!(students["ada@x.edu"].participation_score, 2);
+}
+
+#[test]
+fn record_refuses_unknown_students() {
+    let (_dir, roster) = common::class(Some(ROSTER));
+    let mut store = store::open(roster.as_os_str(), None).unwrap();
+    assert!(cli::record(
+        store.as_mut(),
+        "edsger@x.edu",
+        "answer",
+        &default_outcomes()
+    )
+    .is_err());
+    assert!(store.events().unwrap().is_empty());
+}
+
+#[test]
+fn pick_records_its_draw() {
+    let (_dir, roster) = common::class(Some(ROSTER));
+    let options = Options {
+        seed: Some(1),
+        ..Options::default()
+    };
+    let picked = cli::pick(store::open(roster.as_os_str(), None).unwrap(), &options, 1).unwrap();
+    assert_eq!(picked.len(), 1);
+    let store = store::open(roster.as_os_str(), None).unwrap();
+    assert_eq!(store.draws().unwrap().len(), 1);
+}
+
+#[test]
+fn calling_is_kept_in_the_roster() {
+    let (_dir, roster) = common::class(Some(ROSTER));
+    let mut store = store::open(roster.as_os_str(), None).unwrap();
+    cli::set_calling(store.as_mut(), "alan@x.edu", Calling::Exempt).unwrap();
+    assert_eq!(store.load().unwrap()["alan@x.edu"].calling, Calling::Exempt);
+    assert!(cli::set_calling(store.as_mut(), "edsger@x.edu", Calling::Warn).is_err());
+}
+
+#[test]
+fn each_record_is_a_session_of_its_own() {
+    let (_dir, roster) = common::class(Some(ROSTER));
+    let mut store = store::open(roster.as_os_str(), None).unwrap();
+    cli::record(store.as_mut(), "alan@x.edu", "answer", &default_outcomes()).unwrap();
+    cli::record(store.as_mut(), "ada@x.edu", "answer", &default_outcomes()).unwrap();
+    let events = store.events().unwrap();
+    assert_ne!(events[0].session, events[1].session);
+}
+
+#[test]
+fn least_recent_goes_by_calls_already_committed() {
+    let (_dir, roster) = common::class(Some(ROSTER));
+    let mut store = store::open(roster.as_os_str(), None).unwrap();
+    cli::record(store.as_mut(), "ada@x.edu", "answer", &default_outcomes()).unwrap();
+    cli::record(store.as_mut(), "alan@x.edu", "answer", &default_outcomes()).unwrap();
+    store.commit().unwrap();
+
+    for seed in 0..8 {
+        let store = store::open(roster.as_os_str(), None).unwrap();
+        let options = Options {
+            strategy: "least-recent".to_string(),
+            seed: Some(seed),
+            ..Options::default()
+        };
+        let section = Section::new(String::new(), store, &options).unwrap();
+        // Grace has never been called on.
+        assert_eq!(
+            section.students_view(),
+            &["grace@x.edu", "ada@x.edu", "alan@x.edu"]
+        );
+    }
+}
+
+#[test]
+fn show_has_the_whole_history() {
+    let (_dir, roster) = common::class(Some(ROSTER));
+    let mut store = store::open(roster.as_os_str(), None).unwrap();
+    let before = cli::show(store.as_ref(), "alan@x.edu").unwrap();
+    assert!(before.last_called.is_none());
+
+    let alan = cli::record(store.as_mut(), "alan@x.edu", "answer", &default_outcomes()).unwrap();
+    assert_eq!(alan.participation_score, 1);
+    store.commit().unwrap();
+    cli::record(store.as_mut(), "alan@x.edu", "defer", &default_outcomes()).unwrap();
+    let after = cli::show(store.as_ref(), "alan@x.edu").unwrap();
+    assert_eq!(after.student.history.len(), 2);
+    assert_eq!(after.last_called, Some(after.student.history[1].timestamp));
+    assert_eq!(
+        (after.student.participation_score, after.student.deferrals),
+        (1, 1)
+    );
+    assert!(cli::show(store.as_ref(), "edsger@x.edu").is_err());
+}
+
+#[test]
+fn listing_by_score_leaves_out_unweighted_outcomes() {
+    let (_dir, roster) = common::class(Some(ROSTER));
+    let mut store = store::open(roster.as_os_str(), None).unwrap();
+    let presented = Outcome {
+        name: "presented".to_string(),
+        key: 'p',
+        counter: Counter::ParticipationScore,
+        points: 5,
+        weighted: false,
+    };
+    cli::record(store.as_mut(), "alan@x.edu", "presented", &[presented]).unwrap();
+
+    let listed = |store: &dyn store::Store| -> Vec<String> {
+        cli::list(store, "score")
+            .unwrap()
+            .into_iter()
+            .map(|s| s.email)
+            .collect()
+    };
+    // Alan's five points don't count towards being picked.
+    assert_eq!(
+        listed(store.as_ref()),
+        ["alan@x.edu", "grace@x.edu", "ada@x.edu"]
+    );
+    store.commit().unwrap();
+    assert_eq!(
+        listed(store.as_ref()),
+        ["alan@x.edu", "grace@x.edu", "ada@x.edu"]
+    );
+}
@@ -0,0 +1,95 @@
+// Committing a session, and what it refuses to commit.
+
+use std::{
+    fs,
+    path::Path,
+    time::{Duration, SystemTime},
+};
+
+use participation::{cli, events::default_outcomes, store};
+
+mod common;
+use common::ROSTER;
+
+fn record(roster: &Path, email: &str) {
+    let mut store = store::open(roster.as_os_str(), None).unwrap();
+    cli::record(store.as_mut(), email, "answer", &default_outcomes()).unwrap();
+}
+
+// Every file of the class, with what is in it.
+fn files(dir: &Path) -> Vec<(String, String)> {
+    let mut files: Vec<_> = fs::read_dir(dir)
+        .unwrap()
+        .map(|e| {
+            let path = e.unwrap().path();
+            let name = path.file_name().unwrap().to_string_lossy().into_owned();
+            (name, fs::read_to_string(&path).unwrap())
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+#[test]
+fn committing_promotes_the_output_and_archives_the_rest() {
+    let (dir, roster) = common::class(Some(ROSTER));
+    record(&roster, "alan@x.edu");
+    let out = fs::read_to_string(roster.with_extension("csv.out")).unwrap();
+
+    store::open(roster.as_os_str(), None)
+        .unwrap()
+        .commit()
+        .unwrap();
+    assert_eq!(fs::read_to_string(&roster).unwrap(), out);
+    let archived: Vec<String> = files(dir.path())
+        .into_iter()
+        .filter(|(n, _)| n.starts_with("roster.csv.2"))
+        .map(|(n, _)| n)
+        .collect();
+    assert_eq!(archived.len(), 2, "{archived:?}");
+    let store = store::open(roster.as_os_str(), None).unwrap();
+    assert!(store.events().unwrap().is_empty());
+    let archives = store.archives().unwrap();
+    assert_eq!(archives[0].roster["ada@x.edu"].participation_score, 2);
+    assert_eq!(archives[0].events.len(), 1);
+}
+
+#[test]
+fn output_older_than_the_roster_is_not_committed() {
+    let (dir, roster) = common::class(Some(ROSTER));
+    record(&roster, "alan@x.edu");
+    let out = fs::File::options()
+        .write(true)
+        .open(roster.with_extension("csv.out"))
+        .unwrap();
+    out.set_modified(SystemTime::now() - Duration::from_secs(3600))
+        .unwrap();
+    let before = files(dir.path());
+
+    let err = store::open(roster.as_os_str(), None)
+        .unwrap()
+        .commit()
+        .unwrap_err();
+    assert!(err.to_string().contains("older than"), "{err}");
+    assert_eq!(files(dir.path()), before);
+}
+
+#[test]
+fn output_at_odds_with_the_roster_and_log_is_not_committed() {
+    let (dir, roster) = common::class(Some(ROSTER));
+    record(&roster, "alan@x.edu");
+    let out = roster.with_extension("csv.out");
+    // Someone gave themselves a few points by hand.
+    let edited = fs::read_to_string(&out)
+        .unwrap()
+        .replace("ada@x.edu\t2", "ada@x.edu\t5");
+    fs::write(&out, edited).unwrap();
+    let before = files(dir.path());
+
+    let err = store::open(roster.as_os_str(), None)
+        .unwrap()
+        .commit()
+        .unwrap_err();
+    assert!(err.to_string().contains("ada@x.edu"), "{err}");
+    assert_eq!(files(dir.path()), before);
+}
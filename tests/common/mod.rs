@@ -0,0 +1,24 @@
+// What the integration tests share: a class, with its roster in a
+// directory of its own.
+#![allow(dead_code)]
+
+use std::{fs, path::PathBuf};
+
+use tempfile::TempDir;
+
+pub const ROSTER: &str = "name\temail\tparticipation_score\tdeferrals\tabsent
+Ada Lovelace\tada@x.edu\t2\t0\t0
+Alan Turing\talan@x.edu\t0\t0\t0
+Grace Hopper\tgrace@x.edu\t1\t0\t1
+";
+
+/// A class whose roster.csv is `roster`, or which has yet to have one.
+/// It lasts as long as the directory does.
+pub fn class(roster: Option<&str>) -> (TempDir, PathBuf) {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("roster.csv");
+    if let Some(r) = roster {
+        fs::write(&path, r).unwrap();
+    }
+    (dir, path)
+}
@@ -0,0 +1,110 @@
+// Settings gathered from the user's config, the course's, and the
+// command line.
+
+use std::{fs, path::PathBuf};
+
+use participation::config::{Config, COURSE};
+
+#[test]
+fn relative_paths_are_relative_to_the_config_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join(COURSE);
+    fs::write(
+        &path,
+        "roster = \"rosters/cs101.csv\"\n\
+         output = \"/tmp/cs101.out\"\n\
+         sections = [\"a.csv\", \"rosters/b.csv\"]\n",
+    )
+    .unwrap();
+
+    let config = Config::read(&path).unwrap();
+    assert_eq!(config.roster, Some(dir.path().join("rosters/cs101.csv")));
+    assert_eq!(config.output, Some(PathBuf::from("/tmp/cs101.out")));
+    assert_eq!(
+        config.sections,
+        Some(vec![
+            dir.path().join("a.csv"),
+            dir.path().join("rosters/b.csv")
+        ])
+    );
+}
+
+#[test]
+fn later_settings_take_precedence() {
+    let under = Config {
+        roster: Some("a.csv".into()),
+        strategy: Some("uniform".to_string()),
+        cooldown: Some("3".to_string()),
+        ..Config::default()
+    };
+    let over = Config {
+        sections: Some(vec!["b.csv".into(), "c.csv".into()]),
+        cooldown: Some("10m".to_string()),
+        ..Config::default()
+    };
+
+    let config = under.clone().merge(over);
+    assert_eq!(config.strategy(), "uniform");
+    assert_eq!(config.cooldown.as_deref(), Some("10m"));
+    // The sections say what to open, so the roster goes.
+    assert_eq!(config.roster, None);
+    assert_eq!(config.sections.unwrap().len(), 2);
+
+    let config = under.merge(Config::default());
+    assert_eq!(config.roster, Some("a.csv".into()));
+}
+
+// The only test here that touches the environment, since the tests
+// run side by side.
+#[test]
+fn the_command_line_goes_over_the_course_over_the_user() {
+    let dir = tempfile::tempdir().unwrap();
+    let home = dir.path().join("home");
+    fs::create_dir_all(home.join("participation")).unwrap();
+    std::env::set_var("XDG_CONFIG_HOME", &home);
+    fs::write(
+        home.join("participation/config.toml"),
+        "roster = \"../../course/roster.csv\"\n\
+         delimiter = \",\"\n\
+         strategy = \"uniform\"\n\
+         cooldown = \"3\"\n",
+    )
+    .unwrap();
+    let course = dir.path().join("course");
+    fs::create_dir(&course).unwrap();
+    fs::write(
+        course.join(COURSE),
+        "strategy = \"round-robin\"\ncooldown = \"5\"\n",
+    )
+    .unwrap();
+
+    // The course's config is found next to the roster the user's names.
+    let given = Config {
+        cooldown: Some("10m".to_string()),
+        ..Config::default()
+    };
+    let config = Config::discover(given, None).unwrap();
+    assert_eq!(
+        config.roster,
+        Some(home.join("participation/../../course/roster.csv"))
+    );
+    assert_eq!(config.delimiter.as_deref(), Some(","));
+    assert_eq!(config.strategy(), "round-robin");
+    assert_eq!(config.cooldown.as_deref(), Some("10m"));
+
+    // Or next to the one given.
+    let elsewhere = dir.path().join("elsewhere");
+    fs::create_dir(&elsewhere).unwrap();
+    fs::write(elsewhere.join(COURSE), "strategy = \"lowest\"\n").unwrap();
+    let given = Config {
+        roster: Some(elsewhere.join("roster.csv")),
+        ..Config::default()
+    };
+    let config = Config::discover(given, None).unwrap();
+    assert_eq!(config.strategy(), "lowest");
+    assert_eq!(config.cooldown.as_deref(), Some("3"));
+
+    // Or wherever it is said to be.
+    let config = Config::discover(Config::default(), Some(&course.join(COURSE))).unwrap();
+    assert_eq!(config.strategy(), "round-robin");
+}
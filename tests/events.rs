@@ -0,0 +1,53 @@
+// Outcomes as a course configures them.
+
+use std::fs;
+
+use participation::{config::Config, events::Counter};
+
+#[test]
+fn outcomes_need_names_and_keys_of_their_own() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("participation.toml");
+    let outcomes = |toml: &str| {
+        fs::write(&path, toml).unwrap();
+        Config::read(&path).unwrap().outcomes()
+    };
+
+    let defaults = outcomes("").unwrap();
+    assert!(defaults.iter().any(|o| o.name == "defer" && o.key == 'd'));
+
+    let custom = outcomes(
+        "[[outcomes]]
+name = \"presented\"
+key = \"p\"
+points = 5
+weighted = false
+
+[[outcomes]]
+name = \"late to answer\"
+key = \"l\"
+counter = \"deferrals\"
+",
+    )
+    .unwrap();
+    assert_eq!(custom.len(), 2);
+    assert_eq!(custom[0].counter, Counter::ParticipationScore);
+    assert!(!custom[0].weighted);
+    assert_eq!(
+        (custom[1].counter, custom[1].points),
+        (Counter::Deferrals, 1)
+    );
+
+    let e = outcomes(
+        "[[outcomes]]
+name = \"answer\"
+key = \"a\"
+
+[[outcomes]]
+name = \"absent\"
+key = \"a\"
+",
+    )
+    .unwrap_err();
+    assert!(e.to_string().contains("both recorded with a"), "{e}");
+}
@@ -0,0 +1,80 @@
+// Grades from the roster's counters, and the gradebook uploads they are
+// written as.
+
+use std::collections::HashMap;
+
+use participation::{grade, Student, StudentKey};
+
+fn class() -> HashMap<StudentKey, Student> {
+    [
+        ("Ada Lovelace", "ada@x.edu", 12, 1, 0),
+        ("Alan Turing", "alan@x.edu", 4, 0, 2),
+        ("Grace Hopper", "grace@x.edu", 0, 3, 1),
+    ]
+    .into_iter()
+    .map(|(name, email, answers, deferrals, absent)| {
+        let mut s = Student::new(name.to_string(), email.to_string());
+        s.participation_score = answers;
+        s.deferrals = deferrals;
+        s.absent = absent;
+        (email.to_string(), s)
+    })
+    .collect()
+}
+
+fn formula() -> grade::Formula {
+    grade::Formula {
+        max_answers: Some(10),
+        per_deferral: Some(0.5),
+        per_absence: Some(1.0),
+        ..grade::Formula::default()
+    }
+}
+
+#[test]
+fn answers_are_capped_and_penalties_taken_off() {
+    let grades = formula().grades(&class()).unwrap();
+    assert_eq!(grades["ada@x.edu"], 9.5);
+    assert_eq!(grades["alan@x.edu"], 2.0);
+    // Never below zero.
+    assert_eq!(grades["grace@x.edu"], 0.0);
+}
+
+#[test]
+fn a_curve_gives_the_best_full_points() {
+    let formula = grade::Formula {
+        points: Some(19.0),
+        curve: Some(true),
+        ..formula()
+    };
+    let grades = formula.grades(&class()).unwrap();
+    assert_eq!(grades["ada@x.edu"], 19.0);
+    assert_eq!(grades["alan@x.edu"], 4.0);
+
+    let formula = grade::Formula {
+        points: None,
+        ..formula
+    };
+    assert!(formula.grades(&class()).is_err());
+}
+
+#[test]
+fn canvas_uploads_are_keyed_by_login() {
+    let mut out = Vec::new();
+    grade::write(
+        &mut out,
+        &class(),
+        &formula(),
+        "canvas",
+        "Participation (42)",
+    )
+    .unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "Student,ID,SIS User ID,SIS Login ID,Section,Participation (42)
+Ada Lovelace,,,ada@x.edu,,9.5
+Alan Turing,,,alan@x.edu,,2
+Grace Hopper,,,grace@x.edu,,0
+"
+    );
+}
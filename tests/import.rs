@@ -0,0 +1,94 @@
+// Importing gradebook exports into a roster, and what that leaves on
+// disk.
+
+use std::{fs, path::Path};
+
+use participation::{cli, import::Mapping, store};
+
+mod common;
+
+const ROSTER: &str = "name\temail\tparticipation_score\tdeferrals\tabsent
+Ada Lovelace\tada@x.edu\t2\t1\t0
+";
+
+// As Canvas writes them, with a byte order mark and a row of points.
+const CANVAS: &str = "\u{feff}Student,ID,SIS User ID,SIS Login ID,Section,Quiz 1 (101)
+    Points Possible,,,,,10
+\"Lovelace, Ada\",11,A1,ada@x.edu,CS 101,9
+\"Turing, Alan\",12,A2,alan@x.edu,CS 101,8
+";
+
+const MOODLE: &str = "First name,Surname,ID number,Institution,Department,Email address
+Grace,Hopper,G1,,,grace@x.edu
+Ada,King,A1,,,ada@x.edu
+";
+
+fn import(roster: &Path, export: &str, mapping: &Mapping) -> anyhow::Result<()> {
+    let path = roster.with_file_name("export.csv");
+    fs::write(&path, export).unwrap();
+    let fresh = !roster.exists();
+    let mut store = store::open(roster.as_os_str(), None).unwrap();
+    cli::import(store.as_mut(), fresh, &path, mapping).map(|_| ())
+}
+
+#[test]
+fn canvas_is_merged_keeping_scores() {
+    let (_dir, roster) = common::class(Some(ROSTER));
+    import(&roster, CANVAS, &Mapping::default()).unwrap();
+
+    let students = store::open(roster.as_os_str(), None)
+        .unwrap()
+        .load()
+        .unwrap();
+    assert_eq!(students.len(), 2);
+    assert_eq!(students["ada@x.edu"].participation_score, 2);
+    assert_eq!(students["ada@x.edu"].deferrals, 1);
+    assert_eq!(students["alan@x.edu"].name, "Alan Turing");
+    assert_eq!(students["alan@x.edu"].participation_score, 0);
+}
+
+#[test]
+fn moodle_starts_a_roster() {
+    let (_dir, roster) = common::class(None);
+    import(&roster, MOODLE, &Mapping::default()).unwrap();
+
+    let students = store::open(roster.as_os_str(), None)
+        .unwrap()
+        .load()
+        .unwrap();
+    assert_eq!(students.len(), 2);
+    assert_eq!(students["ada@x.edu"].name, "Ada King");
+    assert_eq!(students["grace@x.edu"].name, "Grace Hopper");
+}
+
+#[test]
+fn columns_can_be_mapped() {
+    let (_dir, roster) = common::class(None);
+    let export = "Surname,Given,Login\nTuring,Alan,alan\n";
+    let mapping = Mapping {
+        first_name: Some("Given".to_string()),
+        last_name: Some("Surname".to_string()),
+        email: Some("Login".to_string()),
+        domain: Some("x.edu".to_string()),
+        ..Mapping::default()
+    };
+    import(&roster, export, &mapping).unwrap();
+
+    let students = store::open(roster.as_os_str(), None)
+        .unwrap()
+        .load()
+        .unwrap();
+    assert_eq!(students["alan@x.edu"].name, "Alan Turing");
+}
+
+#[test]
+fn unknown_columns_are_refused() {
+    let (_dir, roster) = common::class(Some(ROSTER));
+    let mapping = Mapping {
+        email: Some("Mail".to_string()),
+        ..Mapping::default()
+    };
+    let e = import(&roster, MOODLE, &mapping).unwrap_err();
+    assert!(e.to_string().contains("no column \"Mail\""));
+    assert_eq!(fs::read_to_string(&roster).unwrap(), ROSTER);
+}
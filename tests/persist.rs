@@ -0,0 +1,73 @@
+// Replacing files atomically, with a rolling set of backups.
+
+use std::{fs, path::Path, thread, time::Duration};
+
+use participation::persist::{self, BACKUPS};
+
+// The backups of `name` in `dir`, oldest first, by what is in them.
+fn backups(dir: &Path, name: &str) -> Vec<String> {
+    let prefix = format!("{name}.bak.");
+    let mut backups: Vec<_> = fs::read_dir(dir)
+        .unwrap()
+        .map(|e| e.unwrap().path())
+        .filter(|p| {
+            p.file_name()
+                .unwrap()
+                .to_string_lossy()
+                .starts_with(&prefix)
+        })
+        .collect();
+    backups.sort();
+    backups
+        .iter()
+        .map(|p| fs::read_to_string(p).unwrap())
+        .collect()
+}
+
+#[test]
+fn replacing_leaves_only_the_new_contents() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("roster.csv");
+
+    persist::replace(&path, b"first").unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "first");
+    assert!(backups(dir.path(), "roster.csv").is_empty());
+
+    persist::replace(&path, b"second").unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "second");
+    assert_eq!(backups(dir.path(), "roster.csv"), ["first"]);
+    // Nothing is left over from writing it.
+    assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 2);
+}
+
+#[test]
+fn only_the_latest_backups_are_kept() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("roster.csv");
+    for i in 0..BACKUPS + 3 {
+        persist::replace(&path, i.to_string().as_bytes()).unwrap();
+        // The backups are stamped to the millisecond.
+        thread::sleep(Duration::from_millis(2));
+    }
+
+    // The last version is the file itself, and the oldest were pruned.
+    let kept: Vec<String> = (2..BACKUPS + 2).map(|i| i.to_string()).collect();
+    assert_eq!(backups(dir.path(), "roster.csv"), kept);
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        (BACKUPS + 2).to_string()
+    );
+}
+
+#[test]
+fn backups_are_kept_per_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let roster = dir.path().join("roster.csv");
+    let out = dir.path().join("roster.csv.out");
+    persist::replace(&roster, b"roster").unwrap();
+    persist::replace(&out, b"out").unwrap();
+    persist::replace(&out, b"out again").unwrap();
+
+    assert!(backups(dir.path(), "roster.csv").is_empty());
+    assert_eq!(backups(dir.path(), "roster.csv.out"), ["out"]);
+}
@@ -0,0 +1,135 @@
+// Replaying a session's draws, after it has been committed, from
+// either store.
+
+use std::{collections::HashMap, path::Path};
+
+use participation::{
+    app::{Options, Section},
+    calling::Calling,
+    cooldown::Cooldown,
+    events::default_outcomes,
+    replay, store, Student, StudentKey,
+};
+
+fn students(calling: Calling) -> HashMap<StudentKey, Student> {
+    [
+        ("Ada Lovelace", "ada@x.edu", 2),
+        ("Alan Turing", "alan@x.edu", 0),
+        ("Grace Hopper", "grace@x.edu", 1),
+        ("Edsger Dijkstra", "edsger@x.edu", 0),
+        ("Barbara Liskov", "barbara@x.edu", 3),
+    ]
+    .into_iter()
+    .map(|(name, email, answers)| {
+        let mut s = Student::new(name.to_string(), email.to_string());
+        s.participation_score = answers;
+        s.calling = calling;
+        (email.to_string(), s)
+    })
+    .collect()
+}
+
+/// Teach a session that calls on whoever is at the top three times,
+/// and commit it. Returns the session, and each order it showed.
+fn teach(roster: &Path, strategy: &str) -> (String, Vec<Vec<StudentKey>>) {
+    let store = store::open(roster.as_os_str(), None).unwrap();
+    let options = Options {
+        strategy: strategy.to_string(),
+        cooldown: Some(Cooldown::Students(1)),
+        ..Options::default()
+    };
+    let mut section = Section::new(String::new(), store, &options).unwrap();
+    let mut orders = vec![section.students_view().clone()];
+    for _ in 0..3 {
+        section.display_selected_student();
+        section.student_record(&default_outcomes()[0]);
+        orders.push(section.students_view().clone());
+    }
+    assert!(section.commit());
+    (section.session.clone(), orders)
+}
+
+fn replayed(roster: &Path, session: &str) -> Vec<Vec<StudentKey>> {
+    let store = store::open(roster.as_os_str(), None).unwrap();
+    replay::replay(store.as_ref(), session)
+        .unwrap()
+        .into_iter()
+        .map(|r| r.order.into_iter().map(|p| p.email).collect())
+        .collect()
+}
+
+fn write_roster(roster: &Path, calling: Calling) {
+    store::open(roster.as_os_str(), None)
+        .unwrap()
+        .write_roster(&students(calling))
+        .unwrap();
+}
+
+// The second session goes by the history of the first, which has been
+// archived by then.
+fn committed_sessions_replay_as_they_were_drawn(file: &str) {
+    let dir = tempfile::tempdir().unwrap();
+    let roster = dir.path().join(file);
+    write_roster(&roster, Calling::Normal);
+
+    let (first, first_orders) = teach(&roster, "decayed:1sessions");
+    let (second, second_orders) = teach(&roster, "decayed:1sessions");
+    assert_eq!(replayed(&roster, &first), first_orders);
+    assert_eq!(replayed(&roster, &second), second_orders);
+    assert_eq!(replayed(&roster, &second), second_orders);
+
+    let store = store::open(roster.as_os_str(), None).unwrap();
+    let sessions = replay::sessions(store.as_ref()).unwrap();
+    assert_eq!(sessions[&first], 4);
+    assert_eq!(sessions[&second], 4);
+    assert!(replay::replay(store.as_ref(), "never").is_err());
+}
+
+#[test]
+fn committed_csv_sessions_replay_as_they_were_drawn() {
+    committed_sessions_replay_as_they_were_drawn("roster.csv");
+}
+
+#[test]
+fn committed_sqlite_sessions_replay_as_they_were_drawn() {
+    committed_sessions_replay_as_they_were_drawn("roster.db");
+}
+
+// With everyone wanting a warning first, a draw gives whoever is at
+// the top a heads up, and the next puts them at the top.
+fn heads_ups_replay_as_they_were_given(file: &str) {
+    let dir = tempfile::tempdir().unwrap();
+    let roster = dir.path().join(file);
+    write_roster(&roster, Calling::Warn);
+
+    let (session, orders) = teach(&roster, "decayed:1sessions");
+    assert_eq!(replayed(&roster, &session), orders);
+
+    let store = store::open(roster.as_os_str(), None).unwrap();
+    let draws = replay::replay(store.as_ref(), &session).unwrap();
+    let heads_ups: Vec<Option<&str>> = draws
+        .iter()
+        .map(|r| r.order.iter().find(|p| p.heads_up))
+        .map(|p| p.map(|p| p.email.as_str()))
+        .collect();
+    assert!(heads_ups[0].is_some());
+    // Whoever had their heads up is at the top of the next draw, and
+    // is called on without another one.
+    for (r, warned) in draws.iter().skip(1).zip(&heads_ups) {
+        if let Some(warned) = warned {
+            assert_eq!(r.order[0].email, *warned);
+            assert!(r.draw.warned.split_whitespace().any(|k| k == *warned));
+            assert!(!r.order[0].heads_up);
+        }
+    }
+}
+
+#[test]
+fn csv_heads_ups_replay_as_they_were_given() {
+    heads_ups_replay_as_they_were_given("roster.csv");
+}
+
+#[test]
+fn sqlite_heads_ups_replay_as_they_were_given() {
+    heads_ups_replay_as_they_were_given("roster.db");
+}
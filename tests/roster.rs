@@ -0,0 +1,74 @@
+// The roster file's format: its version, reading older versions, and
+// what is said about rosters that don't fit.
+
+use std::fs;
+
+use participation::store;
+use tempfile::TempDir;
+
+const V1: &str = "name\temail\tparticipation_score\tdeferrals\tabsent
+Ada Lovelace\tada@x.edu\t2\t0\t0
+";
+
+fn roster(contents: &str) -> (TempDir, std::path::PathBuf) {
+    let dir = tempfile::tempdir().unwrap();
+    let roster = dir.path().join("roster.csv");
+    fs::write(&roster, contents).unwrap();
+    (dir, roster)
+}
+
+fn load_error(contents: &str) -> String {
+    let (_dir, path) = roster(contents);
+    let store = store::open(path.as_os_str(), None).unwrap();
+    store.load().unwrap_err().to_string()
+}
+
+#[test]
+fn older_rosters_are_migrated_when_written() {
+    let (_dir, path) = roster(V1);
+    let mut store = store::open(path.as_os_str(), None).unwrap();
+    let students = store.load().unwrap();
+    assert!(!students["ada@x.edu"].inactive);
+
+    store.write_roster(&students).unwrap();
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        "# participation roster v3
+name\temail\tparticipation_score\tdeferrals\tabsent\tinactive\tcalling
+Ada Lovelace\tada@x.edu\t2\t0\t0\tfalse\t
+"
+    );
+    assert_eq!(store.load().unwrap()["ada@x.edu"].participation_score, 2);
+}
+
+#[test]
+fn empty_rosters_keep_their_columns() {
+    let (_dir, path) = roster(V1);
+    let mut store = store::open(path.as_os_str(), None).unwrap();
+    store.write_roster(&Default::default()).unwrap();
+    assert!(store.load().unwrap().is_empty());
+}
+
+#[test]
+fn missing_and_unknown_columns_are_named() {
+    let e = load_error("name\temail\tscore\tdeferrals\tabsent\nAda\tada@x.edu\t2\t0\t0\n");
+    assert!(e.contains("missing participation_score"), "{e}");
+    assert!(e.contains("unknown score"), "{e}");
+
+    let e = load_error(
+        "# participation roster v2\nname\temail\tparticipation_score\tdeferrals\tabsent\n",
+    );
+    assert!(e.contains("missing inactive"), "{e}");
+}
+
+#[test]
+fn newer_rosters_are_refused() {
+    let e = load_error(&format!("# participation roster v9\n{V1}"));
+    assert!(e.contains("version 9"), "{e}");
+}
+
+#[test]
+fn a_wrong_delimiter_is_hinted_at() {
+    let e = load_error("name,email,participation_score,deferrals,absent\nAda,ada@x.edu,2,0,0\n");
+    assert!(e.contains("delimiter"), "{e}");
+}
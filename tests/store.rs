@@ -0,0 +1,87 @@
+// Converting a roster between stores, and what comes along.
+
+use std::path::Path;
+
+use participation::{
+    attendance::{Attendance, Record},
+    cli,
+    events::default_outcomes,
+    store,
+};
+
+mod common;
+
+fn record(roster: &Path, email: &str, outcome: &str) {
+    let mut store = store::open(roster.as_os_str(), None).unwrap();
+    cli::record(store.as_mut(), email, outcome, &default_outcomes()).unwrap();
+}
+
+fn convert(from: &Path, to: &Path) {
+    let from = store::open(from.as_os_str(), None).unwrap();
+    let mut to = store::open(to.as_os_str(), None).unwrap();
+    store::copy(from.as_ref(), to.as_mut()).unwrap();
+}
+
+#[test]
+fn the_whole_history_is_converted() {
+    let (dir, csv) = common::class(Some(common::ROSTER));
+    record(&csv, "alan@x.edu", "answer");
+    store::open(csv.as_os_str(), None)
+        .unwrap()
+        .commit()
+        .unwrap();
+    record(&csv, "ada@x.edu", "defer");
+    let taken = [
+        ("2026-09-01", "ada@x.edu", Attendance::Present),
+        ("2026-09-01", "alan@x.edu", Attendance::Late),
+        ("2026-09-03", "alan@x.edu", Attendance::Excused),
+    ];
+    let mut store = store::open(csv.as_os_str(), None).unwrap();
+    for (date, email, status) in taken {
+        store
+            .record_attendance(&Record {
+                date: date.to_string(),
+                email: email.to_string(),
+                status,
+            })
+            .unwrap();
+    }
+    let attendance = |store: &dyn store::Store| -> Vec<_> {
+        store
+            .attendance()
+            .unwrap()
+            .into_iter()
+            .map(|r| (r.date, r.email, r.status))
+            .collect()
+    };
+    let taken: Vec<_> = taken
+        .map(|(date, email, status)| (date.to_string(), email.to_string(), status))
+        .into();
+
+    let db = dir.path().join("roster.db");
+    convert(&csv, &db);
+    let mut sqlite = store::open(db.as_os_str(), None).unwrap();
+    assert_eq!(sqlite.history().unwrap().len(), 2);
+    // Only what was pending is still to be committed.
+    let pending = sqlite.events().unwrap();
+    assert_eq!(pending.len(), 1);
+    assert_eq!(pending[0].email, "ada@x.edu");
+    assert_eq!(attendance(sqlite.as_ref()), taken);
+    let archives = sqlite.archives().unwrap();
+    assert_eq!(archives.len(), 1);
+    assert_eq!(archives[0].roster["alan@x.edu"].participation_score, 0);
+    sqlite.commit().unwrap();
+    let students = sqlite.load().unwrap();
+    assert_eq!(students["alan@x.edu"].participation_score, 1);
+    assert_eq!(students["ada@x.edu"].deferrals, 1);
+
+    // And back again, with both commits archived.
+    let back = dir.path().join("back.csv");
+    convert(&db, &back);
+    let csv = store::open(back.as_os_str(), None).unwrap();
+    assert_eq!(csv.history().unwrap().len(), 2);
+    assert!(csv.events().unwrap().is_empty());
+    assert_eq!(csv.archives().unwrap().len(), 2);
+    assert_eq!(csv.load().unwrap()["ada@x.edu"].deferrals, 1);
+    assert_eq!(attendance(csv.as_ref()), taken);
+}
@@ -0,0 +1,121 @@
+// Syncing the roster with an enrollment list, and what becomes of the
+// students who drop.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use participation::{
+    app::{Options, Section},
+    cli,
+    import::Mapping,
+    store, sync, Student,
+};
+use tempfile::TempDir;
+
+mod common;
+
+const ROSTER: &str = "name\temail\tparticipation_score\tdeferrals\tabsent
+Ada Lovelace\tada@x.edu\t2\t0\t0
+Alan Turing\talan@x.edu\t3\t1\t0
+Grace Hopper\tgrace@x.edu\t1\t0\t1
+";
+
+const ENROLLMENT: &str = "Name,Email
+Ada King,ada@x.edu
+Grace Hopper,grace@x.edu
+Edsger Dijkstra,edsger@x.edu
+";
+
+fn class() -> (TempDir, PathBuf) {
+    let (dir, roster) = common::class(Some(ROSTER));
+    fs::write(dir.path().join("enrollment.csv"), ENROLLMENT).unwrap();
+    (dir, roster)
+}
+
+fn sync(roster: &Path) {
+    let mut store = store::open(roster.as_os_str(), None).unwrap();
+    let enrollment = roster.with_file_name("enrollment.csv");
+    let changes =
+        cli::enrollment_changes(store.as_ref(), &enrollment, &Mapping::default()).unwrap();
+    cli::sync(store.as_mut(), changes).unwrap();
+}
+
+#[test]
+fn changes_are_listed_by_kind() {
+    let (_dir, roster) = class();
+    let students = store::open(roster.as_os_str(), None)
+        .unwrap()
+        .load()
+        .unwrap();
+    let enrolled = vec![
+        Student::new("Ada King".to_string(), "ada@x.edu".to_string()),
+        Student::new("Grace Hopper".to_string(), "grace@x.edu".to_string()),
+        Student::new("Edsger Dijkstra".to_string(), "edsger@x.edu".to_string()),
+    ];
+    let changes: Vec<String> = sync::diff(&students, enrolled)
+        .iter()
+        .map(|c| c.to_string())
+        .collect();
+    assert_eq!(
+        changes,
+        [
+            "add Edsger Dijkstra <edsger@x.edu>",
+            "rename Ada Lovelace to Ada King <ada@x.edu>",
+            "drop Alan Turing <alan@x.edu>",
+        ]
+    );
+}
+
+#[test]
+fn dropped_students_keep_their_history() {
+    let (_dir, roster) = class();
+    sync(&roster);
+
+    let students = store::open(roster.as_os_str(), None)
+        .unwrap()
+        .load()
+        .unwrap();
+    assert_eq!(students.len(), 4);
+    assert!(students["alan@x.edu"].inactive);
+    assert_eq!(students["alan@x.edu"].participation_score, 3);
+    assert_eq!(students["ada@x.edu"].name, "Ada King");
+    assert!(!students["edsger@x.edu"].inactive);
+
+    // Syncing again has nothing left to do, and re-enrolling brings
+    // them back as they were.
+    sync(&roster);
+    fs::write(
+        roster.with_file_name("enrollment.csv"),
+        format!("{ENROLLMENT}Alan Turing,alan@x.edu\n"),
+    )
+    .unwrap();
+    sync(&roster);
+    let students = store::open(roster.as_os_str(), None)
+        .unwrap()
+        .load()
+        .unwrap();
+    assert!(!students["alan@x.edu"].inactive);
+    assert_eq!(students["alan@x.edu"].participation_score, 3);
+}
+
+#[test]
+fn dropped_students_are_not_drawn() {
+    let (_dir, roster) = class();
+    sync(&roster);
+
+    let section = Section::new(
+        "roster".to_string(),
+        store::open(roster.as_os_str(), None).unwrap(),
+        &Options {
+            strategy: "uniform".to_string(),
+            seed: Some(1),
+            ..Options::default()
+        },
+    )
+    .unwrap();
+    assert_eq!(section.students_view().len(), 3);
+    assert!(!section.students_view().contains(&"alan@x.edu".to_string()));
+    assert!(!section.roll.contains(&"alan@x.edu".to_string()));
+}
@@ -0,0 +1,551 @@
+// Driving the terminal UI with scripted keys against a test backend,
+// and checking what it draws and what it leaves in the roster.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    fs,
+    path::PathBuf,
+};
+
+use participation::{
+    app::{App, Options, Section},
+    events::{Counter, Outcome},
+    store, tui, Student, StudentKey,
+};
+use ratatui::{
+    backend::TestBackend,
+    crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers},
+    Terminal,
+};
+use tempfile::TempDir;
+
+mod common;
+use common::ROSTER;
+
+// Biased, but always the same draws.
+fn options() -> Options {
+    Options {
+        seed: Some(1),
+        ..Options::default()
+    }
+}
+
+fn key(code: KeyCode) -> Event {
+    Event::Key(KeyEvent::new(code, KeyModifiers::NONE))
+}
+
+fn ctrl(c: char) -> Event {
+    Event::Key(KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL))
+}
+
+fn typed(s: &str) -> Vec<Event> {
+    s.chars().map(|c| key(KeyCode::Char(c))).collect()
+}
+
+/// A class with a fresh roster for each of its sections in a directory
+/// of its own, and the app running on them.
+struct Class {
+    _dir: TempDir,
+    rosters: Vec<PathBuf>,
+    terminal: Terminal<TestBackend>,
+    app: App,
+}
+
+impl Class {
+    /// Straight after starting, before the roll call is dealt with.
+    fn starting() -> Self {
+        Self::sections(&["roster"])
+    }
+
+    /// Also straight after starting, with a section named for each of
+    /// `names`.
+    fn sections(names: &[&str]) -> Self {
+        Self::sections_of(names, ROSTER)
+    }
+
+    /// Straight after starting, on a roster of `contents`.
+    fn starting_with(contents: &str) -> Self {
+        Self::sections_of(&["roster"], contents)
+    }
+
+    fn sections_of(names: &[&str], contents: &str) -> Self {
+        let dir = tempfile::tempdir().unwrap();
+        let mut rosters = Vec::new();
+        let mut sections = Vec::new();
+        for name in names {
+            let roster = dir.path().join(format!("{name}.csv"));
+            fs::write(&roster, contents).unwrap();
+            sections.push(
+                Section::new(
+                    name.to_string(),
+                    store::open(roster.as_os_str(), None).unwrap(),
+                    &options(),
+                )
+                .unwrap(),
+            );
+            rosters.push(roster);
+        }
+        let terminal = Terminal::new(TestBackend::new(100, 30)).unwrap();
+        Self {
+            _dir: dir,
+            rosters,
+            terminal,
+            app: App::new(sections),
+        }
+    }
+
+    /// With the roll call skipped, so everyone is assumed present.
+    fn new() -> Self {
+        let mut class = Self::starting();
+        class.press([key(KeyCode::Esc)]);
+        class
+    }
+
+    fn press(&mut self, keys: impl IntoIterator<Item = Event>) {
+        let mut script: VecDeque<Event> = keys.into_iter().collect();
+        tui::run_app(
+            &mut self.terminal,
+            &mut self.app,
+            &mut script,
+            &tui::Theme::default(),
+        )
+        .unwrap();
+    }
+
+    /// Select the only student matching `name`, and record `outcome`.
+    fn record(&mut self, name: &str, outcome: char) {
+        self.press([key(KeyCode::Char('s'))]);
+        self.press(typed(name));
+        self.press([key(KeyCode::Enter), key(KeyCode::Char(outcome))]);
+        self.press([key(KeyCode::Esc)]);
+    }
+
+    /// Quit, committing the session to the roster.
+    fn commit(&mut self) {
+        self.press(typed("qy"));
+    }
+
+    /// The (first section's) roster as it is on disk.
+    fn roster(&self) -> HashMap<StudentKey, Student> {
+        self.section_roster(0)
+    }
+
+    fn section_roster(&self, i: usize) -> HashMap<StudentKey, Student> {
+        store::open(self.rosters[i].as_os_str(), None)
+            .unwrap()
+            .load()
+            .unwrap()
+    }
+
+    /// What was drawn last, a line per row.
+    fn screen(&self) -> String {
+        let buffer = self.terminal.backend().buffer();
+        buffer
+            .content()
+            .chunks(buffer.area.width as usize)
+            .map(|row| row.iter().map(|c| c.symbol()).collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+#[test]
+fn starts_with_the_roll_call() {
+    let mut class = Class::starting();
+    class.press([]);
+    let screen = class.screen();
+    assert!(screen.contains("Attendance"));
+    assert!(screen.contains("1/3"));
+    assert!(screen.contains("Ada Lovelace"));
+}
+
+#[test]
+fn absent_students_are_left_out() {
+    let mut class = Class::starting();
+    // The roll is by name: Ada, Alan, Grace.
+    class.press(typed("pap"));
+    let screen = class.screen();
+    assert!(!screen.contains("Attendance"));
+    assert!(screen.contains("Ada Lovelace"));
+    assert!(screen.contains("Grace Hopper"));
+    assert!(!screen.contains("Alan Turing"));
+}
+
+#[test]
+fn no_roll_call_when_everyone_has_dropped() {
+    let mut class = Class::starting_with(
+        "# participation roster v2
+name\temail\tparticipation_score\tdeferrals\tabsent\tinactive
+Ada Lovelace\tada@x.edu\t2\t0\t0\ttrue
+Alan Turing\talan@x.edu\t0\t0\t0\ttrue
+",
+    );
+    class.press(typed("pt"));
+    assert!(class.app.roll_call.is_none());
+    let screen = class.screen();
+    assert!(!screen.contains("Attendance"));
+    assert!(screen.contains("Nobody is enrolled to take attendance for"));
+}
+
+#[test]
+fn no_roll_call_for_an_empty_roster() {
+    let mut class = Class::starting_with("name\temail\tparticipation_score\tdeferrals\tabsent\n");
+    class.press(typed("tpa"));
+    assert!(class.app.roll_call.is_none());
+    assert!(!class.screen().contains("Attendance"));
+}
+
+#[test]
+fn search_narrows_the_list() {
+    let mut class = Class::new();
+    class.press([key(KeyCode::Char('s'))]);
+    class.press(typed("grace"));
+    let screen = class.screen();
+    assert!(screen.contains("grace"));
+    assert!(screen.contains("Grace Hopper"));
+    assert!(!screen.contains("Ada Lovelace"));
+    assert!(!screen.contains("Alan Turing"));
+
+    class.press([key(KeyCode::Esc)]);
+    assert!(class.screen().contains("Ada Lovelace"));
+}
+
+#[test]
+fn arrows_select_a_student() {
+    let mut class = Class::new();
+    let second = class.app.students_view()[1].clone();
+    class.press([key(KeyCode::Down), key(KeyCode::Enter)]);
+
+    let shown = class.app.student_display.as_ref().unwrap();
+    assert_eq!(shown.email, second);
+    assert!(class.screen().contains("Student ✋"));
+    assert!(class.screen().contains(&shown.name));
+
+    class.press([key(KeyCode::Esc)]);
+    assert!(class.app.student_display.is_none());
+}
+
+#[test]
+fn answer_is_committed_to_the_roster() {
+    let mut class = Class::new();
+    class.record("grace", 'a');
+    assert!(class.screen().contains("Recorded answer for Grace Hopper"));
+    class.commit();
+
+    let roster = class.roster();
+    assert_eq!(roster["grace@x.edu"].participation_score, 2);
+    assert_eq!(roster["ada@x.edu"].participation_score, 2);
+    assert_eq!(roster["alan@x.edu"].participation_score, 0);
+}
+
+#[test]
+fn defer_and_absent_are_committed_to_the_roster() {
+    let mut class = Class::new();
+    class.record("ada", 'd');
+    class.record("alan", 'n');
+    class.commit();
+
+    let roster = class.roster();
+    assert_eq!(roster["ada@x.edu"].deferrals, 1);
+    assert_eq!(roster["ada@x.edu"].participation_score, 2);
+    assert_eq!(roster["alan@x.edu"].absent, 1);
+    assert_eq!(roster["grace@x.edu"].absent, 1);
+}
+
+#[test]
+fn undo_takes_an_answer_back() {
+    let mut class = Class::new();
+    class.record("alan", 'a');
+    class.press(typed("u"));
+    assert!(class.screen().contains("Undid answer for Alan Turing"));
+    class.commit();
+
+    assert_eq!(class.roster()["alan@x.edu"].participation_score, 0);
+}
+
+#[test]
+fn redo_puts_back_what_was_undone() {
+    let mut class = Class::new();
+    class.record("alan", 'a');
+    let order = class.app.students_view().clone();
+    class.press(typed("u"));
+    class.press([ctrl('r')]);
+    assert!(class.screen().contains("Redid answer for Alan Turing"));
+    assert_eq!(class.app.students_view(), &order);
+    class.press(typed("u"));
+    class.press([ctrl('r')]);
+    class.commit();
+
+    assert_eq!(class.roster()["alan@x.edu"].participation_score, 1);
+}
+
+#[test]
+fn recording_anew_leaves_nothing_to_redo() {
+    let mut class = Class::new();
+    class.record("alan", 'a');
+    class.press(typed("u"));
+    class.record("grace", 'a');
+    class.press([ctrl('r')]);
+    assert!(class.screen().contains("Nothing to redo"));
+    class.commit();
+
+    let roster = class.roster();
+    assert_eq!(roster["alan@x.edu"].participation_score, 0);
+    assert_eq!(roster["grace@x.edu"].participation_score, 2);
+}
+
+#[test]
+fn graded_answers_and_questions_earn_their_points() {
+    let mut class = Class::new();
+    class.record("alan", '3');
+    assert!(class
+        .screen()
+        .contains("Recorded excellent answer for Alan Turing"));
+    class.record("alan", '1');
+    class.press(typed("u"));
+    assert!(class
+        .screen()
+        .contains("Undid partial answer for Alan Turing"));
+    class.record("grace", '?');
+    assert!(class
+        .screen()
+        .contains("Recorded question for Grace Hopper"));
+    class.commit();
+
+    let roster = class.roster();
+    assert_eq!(roster["alan@x.edu"].participation_score, 3);
+    assert_eq!(roster["grace@x.edu"].participation_score, 2);
+}
+
+#[test]
+fn a_course_can_define_its_own_outcomes() {
+    let class = Class::new();
+    let outcomes = vec![
+        Outcome {
+            name: "presented".to_string(),
+            key: 'p',
+            counter: Counter::ParticipationScore,
+            points: 5,
+            weighted: false,
+        },
+        Outcome {
+            name: "off task".to_string(),
+            key: 'o',
+            counter: Counter::None,
+            points: 1,
+            weighted: true,
+        },
+    ];
+    let mut class = Class {
+        app: App::new(vec![Section::new(
+            "roster".to_string(),
+            store::open(class.rosters[0].as_os_str(), None).unwrap(),
+            &Options {
+                outcomes,
+                ..options()
+            },
+        )
+        .unwrap()]),
+        ..class
+    };
+    class.press([key(KeyCode::Esc)]);
+
+    class.press([key(KeyCode::Char('s'))]);
+    class.press(typed("alan"));
+    class.press([key(KeyCode::Enter)]);
+    let screen = class.screen();
+    assert!(screen.contains("p = presented, o = off task."), "{screen}");
+    assert!(!screen.contains("a = answer"));
+    // Not one of the course's outcomes.
+    class.press(typed("a"));
+    assert!(class.app.student_display.is_some());
+    class.press(typed("p"));
+    assert!(class
+        .screen()
+        .contains("Recorded presented for Alan Turing"));
+    class.press([key(KeyCode::Esc)]);
+
+    // Presenting is worth points, but doesn't sway who is picked.
+    assert_eq!(class.app.students["alan@x.edu"].participation_score, 5);
+    assert_eq!(class.app.students["alan@x.edu"].score(), 0);
+    class.record("grace", 'o');
+    assert!(class
+        .screen()
+        .contains("Recorded off task for Grace Hopper"));
+    class.commit();
+
+    let roster = class.roster();
+    assert_eq!(roster["alan@x.edu"].participation_score, 5);
+    let grace = &roster["grace@x.edu"];
+    assert_eq!(
+        (grace.participation_score, grace.deferrals, grace.absent),
+        (1, 0, 1)
+    );
+}
+
+#[test]
+fn the_help_wraps_onto_as_many_lines_as_it_takes() {
+    let mut class = Class::new();
+    class.terminal = Terminal::new(TestBackend::new(36, 30)).unwrap();
+    class.press([]);
+    let screen = class.screen();
+    let help: Vec<&str> = screen
+        .lines()
+        .take_while(|l| !l.contains("Query"))
+        .collect();
+    assert!(help.len() > 4, "{screen}");
+    // None of it cut off, and no lines left blank.
+    assert!(
+        help.last().unwrap().trim_end().ends_with("students."),
+        "{screen}"
+    );
+    assert!(help.iter().all(|l| !l.trim().is_empty()), "{screen}");
+}
+
+#[test]
+fn quitting_without_changes_leaves_the_roster() {
+    let mut class = Class::new();
+    class.press(typed("q"));
+    assert_eq!(fs::read_to_string(&class.rosters[0]).unwrap(), ROSTER);
+}
+
+#[test]
+fn randomize_draws_everyone_again() {
+    let mut class = Class::new();
+    let roster = class.rosters[0].clone();
+    let draws = || {
+        store::open(roster.as_os_str(), None)
+            .unwrap()
+            .draws()
+            .unwrap()
+            .len()
+    };
+    let before = draws();
+    class.press(typed("r"));
+    assert_eq!(draws(), before + 1);
+
+    let mut view = class.app.students_view().clone();
+    view.sort();
+    assert_eq!(view, ["ada@x.edu", "alan@x.edu", "grace@x.edu"]);
+    for name in ["Ada Lovelace", "Alan Turing", "Grace Hopper"] {
+        assert!(class.screen().contains(name));
+    }
+}
+
+// Switched to mid-session, the round starts after whoever was called
+// last, so they are in it too.
+#[test]
+fn round_robin_calls_on_everyone_before_anyone_again() {
+    let mut class = Class::new();
+    class.record("alan", 'a');
+    class.press(typed("mm"));
+    assert!(class.screen().contains("Selecting students by round-robin"));
+
+    let round = class.app.students_view().clone();
+    assert_eq!(round.len(), 3);
+    let mut called = Vec::new();
+    for _ in 0..2 * round.len() {
+        called.push(class.app.students_view()[0].clone());
+        class.press([key(KeyCode::Enter), key(KeyCode::Char('a'))]);
+    }
+    assert_eq!(called[..3], round);
+    let mut again = called[3..].to_vec();
+    again.sort();
+    assert_eq!(again, ["ada@x.edu", "alan@x.edu", "grace@x.edu"]);
+}
+
+#[test]
+fn sections_are_kept_apart() {
+    let mut class = Class::sections(&["cs101-01", "cs101-02"]);
+    // Each section has its own roll call.
+    class.press([key(KeyCode::Esc), key(KeyCode::Tab), key(KeyCode::Esc)]);
+    let screen = class.screen();
+    assert!(screen.contains("cs101-01"));
+    assert!(screen.contains("cs101-02"));
+
+    class.record("grace", 'a');
+    assert!(class.screen().contains("cs101-02*"));
+    assert_eq!(class.app.students["grace@x.edu"].answered_today, 1);
+    class.press([key(KeyCode::BackTab)]);
+    assert_eq!(class.app.current, 0);
+    assert_eq!(class.app.students["grace@x.edu"].answered_today, 0);
+    assert!(!class.screen().contains("Recorded answer"));
+
+    class.commit();
+    assert_eq!(
+        class.section_roster(1)["grace@x.edu"].participation_score,
+        2
+    );
+    assert_eq!(fs::read_to_string(&class.rosters[0]).unwrap(), ROSTER);
+}
+
+#[test]
+fn details_show_the_history_without_recording() {
+    let mut class = Class::new();
+    class.record("alan", 'a');
+    class.record("alan", 'd');
+    class.press([key(KeyCode::Char('s'))]);
+    class.press(typed("alan"));
+    class.press([ctrl('o')]);
+
+    let screen = class.screen();
+    assert!(screen.contains("Alan Turing <alan@x.edu>"));
+    assert!(screen.contains("answers 1, deferrals 1, absent 0"));
+    assert!(screen.contains("Points per session"));
+    assert!(screen.contains("  defer"));
+    assert!(screen.contains("  answer"));
+
+    // Outcome keys do nothing here.
+    let roster = class.rosters[0].clone();
+    let events = || {
+        store::open(roster.as_os_str(), None)
+            .unwrap()
+            .events()
+            .unwrap()
+            .len()
+    };
+    let before = events();
+    class.press(typed("ad"));
+    assert_eq!(events(), before);
+    class.press([key(KeyCode::Esc)]);
+    assert!(class.app.detail.is_none());
+}
+
+#[test]
+fn notes_and_tags_are_kept_and_searchable() {
+    let mut class = Class::new();
+    class.press([key(KeyCode::Char('s'))]);
+    class.press(typed("grace"));
+    class.press([ctrl('o')]);
+    class.press(typed("nprefers written questions"));
+    class.press([key(KeyCode::Enter)]);
+    class.press(typed("t#accommodation week3"));
+    class.press([key(KeyCode::Enter)]);
+    assert!(class.screen().contains("notes: prefers written questions"));
+    assert!(class.screen().contains("tags: #accommodation #week3"));
+    class.press([key(KeyCode::Esc), key(KeyCode::Esc)]);
+
+    // Kept without a commit, and shown in the list.
+    let mut class = Class {
+        app: App::new(vec![Section::new(
+            "roster".to_string(),
+            store::open(class.rosters[0].as_os_str(), None).unwrap(),
+            &options(),
+        )
+        .unwrap()]),
+        ..class
+    };
+    class.press([key(KeyCode::Esc)]);
+    let screen = class.screen();
+    assert!(screen.contains("📝"));
+    assert!(screen.contains("#accommodation #week3"));
+
+    class.press([key(KeyCode::Char('s'))]);
+    class.press(typed("#acc"));
+    assert!(class.screen().contains("Grace Hopper"));
+    assert!(!class.screen().contains("Ada Lovelace"));
+    class.press([key(KeyCode::Esc), key(KeyCode::Char('s'))]);
+    class.press(typed("written"));
+    assert!(class.screen().contains("Grace Hopper"));
+    assert!(!class.screen().contains("Alan Turing"));
+}